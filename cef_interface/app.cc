@@ -84,7 +84,52 @@ void MyApp::OnContextInitialized() {
   }
 }
 
+bool PostMessageHandler::Execute(const CefString& name,
+                                 CefRefPtr<CefV8Value> object,
+                                 const CefV8ValueList& arguments,
+                                 CefRefPtr<CefV8Value>& retval,
+                                 CefString& exception) {
+  // this is called in the render sub-process
+
+  if (arguments.size() != 1) {
+    exception = "postMessage takes 1 argument";
+    return true;
+  }
+
+  std::string json;
+  if (!json_stringify(arguments[0].get(), json)) {
+    exception = "postMessage argument can't be turned into JSON";
+    return true;
+  }
+
+  auto message = CefProcessMessage::Create("PageMessage");
+  message->GetArgumentList()->SetString(0, json);
+  CefV8Context::GetCurrentContext()->GetFrame()->SendProcessMessage(
+      PID_BROWSER, message);
+
+  return true;
+}
+
 // CefRenderProcessHandler methods:
+void MyApp::OnContextCreated(CefRefPtr<CefBrowser> browser,
+                             CefRefPtr<CefFrame> frame,
+                             CefRefPtr<CefV8Context> context) {
+  // this is called in the render sub-process
+
+  // iframes and websites could fake events like "ended"
+  if (!is_player_page(frame)) {
+    return;
+  }
+
+  auto cef = CefV8Value::CreateObject(nullptr, nullptr);
+  cef->SetValue("postMessage",
+                CefV8Value::CreateFunction("postMessage",
+                                           new PostMessageHandler()),
+                V8_PROPERTY_ATTRIBUTE_READONLY);
+
+  context->GetGlobal()->SetValue("cef", cef, V8_PROPERTY_ATTRIBUTE_READONLY);
+}
+
 bool MyApp::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
                                      CefRefPtr<CefFrame> frame,
                                      CefProcessId source_process,
@@ -114,11 +159,11 @@ bool MyApp::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
     context->Enter();
     bool success =
         context->Eval(script, script_url, start_line, result, exception);
-    context->Exit();
 
     FFIRustV8Response v8_response;
     if (success) {
       v8_response.success = true;
+      // still entered, arrays and objects are turned into JSON
       v8_response.result = create_rust_v8_value(result.get());
 
     } else {
@@ -128,6 +173,7 @@ bool MyApp::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
       v8_response.success = false;
       v8_response.error = true;
     }
+    context->Exit();
 
     auto serialized = serialize_v8_response(v8_response);
     response_args->SetBinary(1, serialized);
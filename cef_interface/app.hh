@@ -5,6 +5,22 @@
 #include "client.hh"
 #include "interface.hh"
 
+// window.cef.postMessage(value), sends value as JSON to the browser process
+class PostMessageHandler : public CefV8Handler {
+ public:
+  PostMessageHandler() {}
+
+  bool Execute(const CefString& name,
+               CefRefPtr<CefV8Value> object,
+               const CefV8ValueList& arguments,
+               CefRefPtr<CefV8Value>& retval,
+               CefString& exception) override;
+
+ private:
+  IMPLEMENT_REFCOUNTING(PostMessageHandler);
+  DISALLOW_COPY_AND_ASSIGN(PostMessageHandler);
+};
+
 class MyApp : public CefApp,
               public CefBrowserProcessHandler,
               public CefRenderProcessHandler {
@@ -26,6 +42,10 @@ class MyApp : public CefApp,
   void OnContextInitialized() override;
 
   // CefRenderProcessHandler methods:
+  void OnContextCreated(CefRefPtr<CefBrowser> browser,
+                        CefRefPtr<CefFrame> frame,
+                        CefRefPtr<CefV8Context> context) override;
+
   bool OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
                                 CefRefPtr<CefFrame> frame,
                                 CefProcessId source_process,
@@ -2,6 +2,17 @@
 
 #include "serialize.hh"
 
+bool is_player_page(CefRefPtr<CefFrame> frame) {
+  if (!frame || !frame->IsMain()) {
+    return false;
+  }
+
+  // keep in sync with TWITCH_PAGE_URL in src/cef/bindings/mod.rs
+  std::string url = frame->GetURL().ToString();
+  return url.rfind("local://", 0) == 0 ||
+         url.rfind("https://localhost/cef-twitch/", 0) == 0;
+}
+
 MyClient::MyClient(Callbacks callbacks_) {
   this->callbacks = callbacks_;
 }
@@ -56,6 +67,17 @@ bool MyClient::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
     return true;
   }
 
+  if (message_name == "PageMessage") {
+    // the renderer only binds it on our pages, but don't trust it
+    if (callbacks.on_page_message && is_player_page(frame)) {
+      auto json = message->GetArgumentList()->GetString(0).ToString();
+      callbacks.on_page_message(cef_interface_add_ref_browser(browser.get()),
+                                json.c_str());
+    }
+
+    return true;
+  }
+
   return false;
 }
 
@@ -70,8 +92,10 @@ void MyClient::OnTitleChange(CefRefPtr<CefBrowser> browser,
 }
 void MyClient::OnLoadingProgressChange(CefRefPtr<CefBrowser> browser,
                                        double progress) {
-  // auto ag = std::to_string(progress);
-  // rust_debug(ag.c_str());
+  if (callbacks.on_loading_progress) {
+    callbacks.on_loading_progress(cef_interface_add_ref_browser(browser.get()),
+                                  progress);
+  }
 }
 
 // CefLifeSpanHandler methods:
@@ -108,7 +132,15 @@ bool MyClient::OnBeforePopup(
     bool* no_javascript_access) {
   rust_debug("popup detected");
 
-  frame->LoadURL(target_url);
+  if (callbacks.on_before_popup) {
+    auto target_url_utf8 = target_url.ToString();
+    bool load_here = callbacks.on_before_popup(
+        cef_interface_add_ref_browser(browser.get()), target_url_utf8.c_str());
+
+    if (load_here) {
+      frame->LoadURL(target_url);
+    }
+  }
 
   // block the popup
   return true;
@@ -144,6 +176,37 @@ void MyClient::OnPaint(CefRefPtr<CefBrowser> browser,
 }
 
 // CefLoadHandler methods:
+void MyClient::OnLoadStart(CefRefPtr<CefBrowser> browser,
+                           CefRefPtr<CefFrame> frame,
+                           TransitionType transition_type) {
+  if (frame->IsMain()) {
+    if (callbacks.on_load_start) {
+      callbacks.on_load_start(cef_interface_add_ref_browser(browser.get()));
+    }
+  }
+}
+
+void MyClient::OnLoadError(CefRefPtr<CefBrowser> browser,
+                           CefRefPtr<CefFrame> frame,
+                           ErrorCode errorCode,
+                           const CefString& errorText,
+                           const CefString& failedUrl) {
+  // aborted when we navigate away or stop loading
+  if (errorCode == ERR_ABORTED) {
+    return;
+  }
+
+  if (frame->IsMain()) {
+    if (callbacks.on_load_error) {
+      auto error_text_utf8 = errorText.ToString();
+      auto failed_url_utf8 = failedUrl.ToString();
+      callbacks.on_load_error(cef_interface_add_ref_browser(browser.get()),
+                              errorCode, error_text_utf8.c_str(),
+                              failed_url_utf8.c_str());
+    }
+  }
+}
+
 void MyClient::OnLoadEnd(CefRefPtr<CefBrowser> browser,
                          CefRefPtr<CefFrame> frame,
                          int httpStatusCode) {
@@ -154,6 +217,23 @@ void MyClient::OnLoadEnd(CefRefPtr<CefBrowser> browser,
   }
 }
 
+// fix for some embedded youtube videos giving "video unavailable"
+// something to do with referrer not being set from our data: url
+static bool NeedsYoutubeReferrer(CefRefPtr<CefFrame> frame,
+                                 CefRefPtr<CefRequest> request) {
+  auto referrer_url = request->GetReferrerURL();
+  if (!referrer_url.c_str()) {
+    std::string url = request->GetURL();
+    auto main_url = frame->GetURL();
+
+    if (main_url == "" && url.rfind("https://www.youtube.com/embed/", 0) == 0) {
+      return true;
+    }
+  }
+
+  return false;
+}
+
 // CefRequestHandler methods:
 CefRefPtr<CefResourceRequestHandler> MyClient::GetResourceRequestHandler(
     CefRefPtr<CefBrowser> browser,
@@ -163,18 +243,34 @@ CefRefPtr<CefResourceRequestHandler> MyClient::GetResourceRequestHandler(
     bool is_download,
     const CefString& request_initiator,
     bool& disable_default_handling) {
-  auto referrer_url = request->GetReferrerURL();
-  if (!referrer_url.c_str()) {
+  bool has_blocklist = callbacks.on_before_resource_load &&
+                       callbacks.has_blocklist && callbacks.has_blocklist();
+  if (has_blocklist || NeedsYoutubeReferrer(frame, request)) {
+    // use MyClient::OnBeforeResourceLoad
+    return this;
+  }
+
+  return nullptr;
+}
+
+bool MyClient::OnBeforeBrowse(CefRefPtr<CefBrowser> browser,
+                              CefRefPtr<CefFrame> frame,
+                              CefRefPtr<CefRequest> request,
+                              bool user_gesture,
+                              bool is_redirect) {
+  if (callbacks.on_before_browse) {
     std::string url = request->GetURL();
-    auto main_url = frame->GetURL();
+    bool allow = callbacks.on_before_browse(
+        cef_interface_add_ref_browser(browser.get()), url.c_str(),
+        frame->IsMain());
 
-    if (main_url == "" && url.rfind("https://www.youtube.com/embed/", 0) == 0) {
-      // use MyClient::OnBeforeResourceLoad
-      return this;
+    if (!allow) {
+      // Return true to cancel the navigation
+      return true;
     }
   }
 
-  return nullptr;
+  return false;
 }
 
 bool MyClient::OnCertificateError(CefRefPtr<CefBrowser> browser,
@@ -205,11 +301,18 @@ CefResourceRequestHandler::ReturnValue MyClient::OnBeforeResourceLoad(
     CefRefPtr<CefFrame> frame,
     CefRefPtr<CefRequest> request,
     CefRefPtr<CefCallback> callback) {
-  // fix for some embedded youtube videos giving "video unavailable"
-  // something to do with referrer not being set from our data: url
-  auto new_referrer_url = L"https://www.youtube.com/";
-  request->SetReferrer(new_referrer_url,
-                       CefRequest::ReferrerPolicy::REFERRER_POLICY_DEFAULT);
+  if (callbacks.on_before_resource_load) {
+    std::string url = request->GetURL();
+    if (callbacks.on_before_resource_load(url.c_str())) {
+      return CefResourceRequestHandler::ReturnValue::RV_CANCEL;
+    }
+  }
+
+  if (NeedsYoutubeReferrer(frame, request)) {
+    auto new_referrer_url = L"https://www.youtube.com/";
+    request->SetReferrer(new_referrer_url,
+                         CefRequest::ReferrerPolicy::REFERRER_POLICY_DEFAULT);
+  }
 
   return CefResourceRequestHandler::ReturnValue::RV_CONTINUE;
 }
@@ -219,8 +322,17 @@ bool MyClient::OnBeforeUnloadDialog(CefRefPtr<CefBrowser> browser,
                                     const CefString& message_text,
                                     bool is_reload,
                                     CefRefPtr<CefJSDialogCallback> callback) {
-  CefString user_input;
-  callback->Continue(true, user_input);
+  if (callbacks.on_js_dialog) {
+    auto message_text_utf8 = message_text.ToString();
+    callbacks.on_js_dialog(
+        cef_interface_add_ref_browser(browser.get()),
+        cef_interface_add_ref_js_dialog_callback(callback.get()), 3,
+        message_text_utf8.c_str(), "");
+  } else {
+    CefString user_input;
+    callback->Continue(true, user_input);
+  }
+
   // Return true if the application will use a custom dialog or if the callback
   // has been executed immediately.
   return true;
@@ -233,6 +345,18 @@ bool MyClient::OnJSDialog(CefRefPtr<CefBrowser> browser,
                           const CefString& default_prompt_text,
                           CefRefPtr<CefJSDialogCallback> callback,
                           bool& suppress_message) {
+  if (callbacks.on_js_dialog) {
+    auto message_text_utf8 = message_text.ToString();
+    auto default_prompt_text_utf8 = default_prompt_text.ToString();
+    callbacks.on_js_dialog(
+        cef_interface_add_ref_browser(browser.get()),
+        cef_interface_add_ref_js_dialog_callback(callback.get()), dialog_type,
+        message_text_utf8.c_str(), default_prompt_text_utf8.c_str());
+
+    // answered later from chat
+    return true;
+  }
+
   // Set |suppress_message| to true and return false to suppress the message
   suppress_message = true;
   return false;
@@ -285,7 +409,32 @@ bool MyClient::OnFileDialog(CefRefPtr<CefBrowser> browser,
 bool MyClient::CanDownload(CefRefPtr<CefBrowser> browser,
                            const CefString& url,
                            const CefString& request_method) {
-  return false;
+  // OnBeforeDownload decides
+  return callbacks.on_before_download != nullptr;
+}
+
+// Not continuing the callback cancels the download.
+void MyClient::StartDownload(CefRefPtr<CefBrowser> browser,
+                             CefRefPtr<CefDownloadItem> download_item,
+                             const CefString& suggested_name,
+                             CefRefPtr<CefBeforeDownloadCallback> callback) {
+  if (!callbacks.on_before_download) {
+    return;
+  }
+
+  auto url_utf8 = download_item->GetURL().ToString();
+  auto suggested_name_utf8 = suggested_name.ToString();
+  auto path = callbacks.on_before_download(
+      cef_interface_add_ref_browser(browser.get()), download_item->GetId(),
+      url_utf8.c_str(), suggested_name_utf8.c_str(),
+      download_item->GetTotalBytes());
+
+  std::string path_utf8(path.ptr, path.len);
+  cef_interface_delete_ref_string(path.ptr);
+
+  if (!path_utf8.empty()) {
+    callback->Continue(path_utf8, false);
+  }
 }
 
 #if CEF_VERSION_MAJOR >= 125
@@ -293,9 +442,9 @@ bool MyClient::OnBeforeDownload(CefRefPtr<CefBrowser> browser,
                                 CefRefPtr<CefDownloadItem> download_item,
                                 const CefString& suggested_name,
                                 CefRefPtr<CefBeforeDownloadCallback> callback) {
-  // By default the download will be canceled.
-  rust_debug("OnBeforeDownload");
-  // Return false to proceed with default handling
+  StartDownload(browser, download_item, suggested_name, callback);
+  // Return false to proceed with default handling, which would ask where to
+  // save it
   return true;
 }
 #else
@@ -303,16 +452,27 @@ void MyClient::OnBeforeDownload(CefRefPtr<CefBrowser> browser,
                                 CefRefPtr<CefDownloadItem> download_item,
                                 const CefString& suggested_name,
                                 CefRefPtr<CefBeforeDownloadCallback> callback) {
-  // By default the download will be canceled.
-  rust_debug("OnBeforeDownload");
+  StartDownload(browser, download_item, suggested_name, callback);
 }
 #endif
 
 void MyClient::OnDownloadUpdated(CefRefPtr<CefBrowser> browser,
                                  CefRefPtr<CefDownloadItem> download_item,
                                  CefRefPtr<CefDownloadItemCallback> callback) {
-  rust_debug("OnDownloadUpdated");
+  if (callbacks.on_download_updated) {
+    bool keep_going = callbacks.on_download_updated(
+        cef_interface_add_ref_browser(browser.get()), download_item->GetId(),
+        download_item->GetReceivedBytes(), download_item->GetTotalBytes(),
+        download_item->IsComplete(), download_item->IsCanceled());
+
+    if (keep_going) {
+      return;
+    }
+  }
+
   // Execute |callback| either asynchronously or in this method to cancel the
   // download if desired.
-  callback->Cancel();
+  if (!download_item->IsComplete() && !download_item->IsCanceled()) {
+    callback->Cancel();
+  }
 }
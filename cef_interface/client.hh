@@ -8,6 +8,9 @@
 
 #include "interface.hh"
 
+// main frame of one of our player pages, the only place window.cef exists
+bool is_player_page(CefRefPtr<CefFrame> frame);
+
 class MyClient : public CefClient,
                  public CefDisplayHandler,
                  public CefLifeSpanHandler,
@@ -71,6 +74,14 @@ class MyClient : public CefClient,
                int height) override;
 
   // CefLoadHandler methods:
+  void OnLoadStart(CefRefPtr<CefBrowser> browser,
+                   CefRefPtr<CefFrame> frame,
+                   TransitionType transition_type) override;
+  void OnLoadError(CefRefPtr<CefBrowser> browser,
+                   CefRefPtr<CefFrame> frame,
+                   ErrorCode errorCode,
+                   const CefString& errorText,
+                   const CefString& failedUrl) override;
   void OnLoadEnd(CefRefPtr<CefBrowser> browser,
                  CefRefPtr<CefFrame> frame,
                  int httpStatusCode) override;
@@ -85,6 +96,12 @@ class MyClient : public CefClient,
       const CefString& request_initiator,
       bool& disable_default_handling) override;
 
+  bool OnBeforeBrowse(CefRefPtr<CefBrowser> browser,
+                      CefRefPtr<CefFrame> frame,
+                      CefRefPtr<CefRequest> request,
+                      bool user_gesture,
+                      bool is_redirect) override;
+
   bool OnCertificateError(CefRefPtr<CefBrowser> browser,
                           cef_errorcode_t cert_error,
                           const CefString& request_url,
@@ -161,6 +178,11 @@ class MyClient : public CefClient,
                          CefRefPtr<CefDownloadItem> download_item,
                          CefRefPtr<CefDownloadItemCallback> callback) override;
 
+  void StartDownload(CefRefPtr<CefBrowser> browser,
+                     CefRefPtr<CefDownloadItem> download_item,
+                     const CefString& suggested_name,
+                     CefRefPtr<CefBeforeDownloadCallback> callback);
+
  private:
   Callbacks callbacks;
 
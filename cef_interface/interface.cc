@@ -1,6 +1,7 @@
 #include "interface.hh"
 
 #include <include/base/cef_bind.h>
+#include <include/cef_jsdialog_handler.h>
 #include <include/cef_origin_whitelist.h>
 #include <include/cef_request_context.h>
 #include <include/cef_request_context_handler.h>
@@ -59,6 +60,27 @@ extern "C" int cef_interface_release_ref_browser(CefBrowser* browser) {
   return 0;
 }
 
+extern "C" RustRefJSDialogCallback cef_interface_add_ref_js_dialog_callback(
+    CefJSDialogCallback* callback) {
+  callback->AddRef();
+
+  RustRefJSDialogCallback r;
+  r.ptr = callback;
+  return r;
+}
+extern "C" int cef_interface_release_ref_js_dialog_callback(
+    CefJSDialogCallback* callback) {
+  callback->Release();
+  return 0;
+}
+extern "C" int cef_interface_js_dialog_callback_continue(
+    CefJSDialogCallback* callback,
+    bool success,
+    const char* user_input) {
+  callback->Continue(success, user_input);
+  return 0;
+}
+
 extern "C" RustRefString cef_interface_new_ref_string(const char* c_str,
                                                       size_t len) {
   char* copy = new char[len + 1]();
@@ -203,6 +225,13 @@ extern "C" int cef_interface_initialize(MyApp* app, CefInitializePaths paths) {
     return -1;
   }
 
+  // pages that need a real domain, like Twitch's embeds checking their parent
+  if (!CefRegisterSchemeHandlerFactory("https", "localhost",
+                                       new LocalSchemeHandlerFactory())) {
+    rust_warn("CefRegisterSchemeHandlerFactory https failed!");
+    return -1;
+  }
+
   // if (!CefAddCrossOriginWhitelistEntry("local://media", "http", "", true)) {
   //   rust_warn("CefAddCrossOriginWhitelistEntry failed!");
   //   return -1;
@@ -395,6 +424,75 @@ extern "C" int cef_interface_browser_send_click(CefBrowser* browser,
   return 0;
 }
 
+extern "C" int cef_interface_browser_send_mouse_move(CefBrowser* browser,
+                                                     int x,
+                                                     int y,
+                                                     uint32_t modifiers,
+                                                     bool mouse_leave) {
+  auto browser_host = browser->GetHost();
+
+  CefMouseEvent event = CefMouseEvent();
+  event.x = x;
+  event.y = y;
+  event.modifiers = modifiers;
+
+  browser_host->SendMouseMoveEvent(event, mouse_leave);
+
+  return 0;
+}
+
+extern "C" int cef_interface_browser_send_mouse_button(CefBrowser* browser,
+                                                       int x,
+                                                       int y,
+                                                       uint32_t modifiers,
+                                                       int button,
+                                                       bool mouse_up,
+                                                       int click_count) {
+  CefBrowserHost::MouseButtonType button_type;
+  switch (button) {
+    case 0:
+      button_type = CefBrowserHost::MouseButtonType::MBT_LEFT;
+      break;
+    case 1:
+      button_type = CefBrowserHost::MouseButtonType::MBT_MIDDLE;
+      break;
+    case 2:
+      button_type = CefBrowserHost::MouseButtonType::MBT_RIGHT;
+      break;
+    default:
+      return -1;
+  }
+
+  auto browser_host = browser->GetHost();
+
+  CefMouseEvent event = CefMouseEvent();
+  event.x = x;
+  event.y = y;
+  event.modifiers = modifiers;
+
+  browser_host->SendMouseClickEvent(event, button_type, mouse_up, click_count);
+
+  return 0;
+}
+
+extern "C" int cef_interface_browser_send_mouse_wheel(CefBrowser* browser,
+                                                      int x,
+                                                      int y,
+                                                      uint32_t modifiers,
+                                                      int delta_x,
+                                                      int delta_y) {
+  auto browser_host = browser->GetHost();
+
+  CefMouseEvent event = CefMouseEvent();
+  event.x = x;
+  event.y = y;
+  event.modifiers = modifiers;
+
+  browser_host->SendMouseWheelEvent(event, delta_x, delta_y);
+
+  return 0;
+}
+
 extern "C" int cef_interface_browser_send_text(CefBrowser* browser,
                                                const char* text) {
   auto browser_host = browser->GetHost();
@@ -413,16 +511,93 @@ extern "C" int cef_interface_browser_send_text(CefBrowser* browser,
   return 0;
 }
 
+extern "C" int cef_interface_browser_send_key(CefBrowser* browser,
+                                              int type,
+                                              uint32_t modifiers,
+                                              int windows_key_code,
+                                              uint16_t character) {
+  cef_key_event_type_t event_type;
+  switch (type) {
+    case KEYEVENT_RAWKEYDOWN:
+    case KEYEVENT_KEYDOWN:
+    case KEYEVENT_KEYUP:
+    case KEYEVENT_CHAR:
+      event_type = static_cast<cef_key_event_type_t>(type);
+      break;
+    default:
+      return -1;
+  }
+
+  auto browser_host = browser->GetHost();
+
+  CefKeyEvent event = CefKeyEvent();
+  event.type = event_type;
+  event.modifiers = modifiers;
+  event.windows_key_code = windows_key_code;
+  event.native_key_code = windows_key_code;
+  event.character = character;
+  event.unmodified_character = character;
+
+  browser_host->SendKeyEvent(event);
+
+  return 0;
+}
+
 extern "C" int cef_interface_browser_reload(CefBrowser* browser) {
   browser->ReloadIgnoreCache();
   return 0;
 }
 
+extern "C" int cef_interface_browser_go_back(CefBrowser* browser) {
+  browser->GoBack();
+  return 0;
+}
+
+extern "C" int cef_interface_browser_go_forward(CefBrowser* browser) {
+  browser->GoForward();
+  return 0;
+}
+
+extern "C" bool cef_interface_browser_can_go_back(CefBrowser* browser) {
+  return browser->CanGoBack();
+}
+
+extern "C" bool cef_interface_browser_can_go_forward(CefBrowser* browser) {
+  return browser->CanGoForward();
+}
+
+extern "C" int cef_interface_browser_stop_load(CefBrowser* browser) {
+  browser->StopLoad();
+  return 0;
+}
+
+extern "C" RustRefString cef_interface_browser_get_url(CefBrowser* browser) {
+  std::string url;
+
+  auto frame = browser->GetMainFrame();
+  if (frame) {
+    url = frame->GetURL().ToString();
+  }
+
+  return cef_interface_new_ref_string(url.c_str(), url.length());
+}
+
 extern "C" int cef_interface_browser_was_resized(CefBrowser* browser) {
   browser->GetHost()->WasResized();
   return 0;
 }
 
+extern "C" int cef_interface_browser_invalidate(CefBrowser* browser) {
+  browser->GetHost()->Invalidate(PET_VIEW);
+  return 0;
+}
+
+extern "C" int cef_interface_browser_set_zoom_level(CefBrowser* browser,
+                                                    double zoom_level) {
+  browser->GetHost()->SetZoomLevel(zoom_level);
+  return 0;
+}
+
 extern "C" int cef_interface_browser_open_dev_tools(CefBrowser* browser) {
   auto browser_host = browser->GetHost();
 
@@ -9,6 +9,7 @@ class MyApp;
 class MyClient;
 class CefBrowser;
 class CefV8Value;
+class CefJSDialogCallback;
 
 struct RustRefApp {
   MyApp* ptr;
@@ -31,6 +32,20 @@ struct RustRefBrowser {
 extern "C" RustRefBrowser cef_interface_add_ref_browser(CefBrowser* browser);
 extern "C" int cef_interface_release_ref_browser(CefBrowser* browser);
 
+struct RustRefJSDialogCallback {
+  CefJSDialogCallback* ptr;
+};
+
+extern "C" RustRefJSDialogCallback cef_interface_add_ref_js_dialog_callback(
+    CefJSDialogCallback* callback);
+extern "C" int cef_interface_release_ref_js_dialog_callback(
+    CefJSDialogCallback* callback);
+/// Answer the dialog, can only be called once
+extern "C" int cef_interface_js_dialog_callback_continue(
+    CefJSDialogCallback* callback,
+    bool success,
+    const char* user_input);
+
 struct RustRefString {
   const char* ptr;
   size_t len;
@@ -59,6 +74,19 @@ typedef void (*OnPaintCallback)(RustRefBrowser browser,
 /// Called when the browser is done loading the MAIN frame.
 typedef void (*OnLoadEndCallback)(RustRefBrowser browser);
 
+/// Called when the browser starts loading the MAIN frame.
+typedef void (*OnLoadStartCallback)(RustRefBrowser browser);
+
+/// Called when the overall page loading progress changes, from 0.0 to 1.0.
+typedef void (*OnLoadingProgressCallback)(RustRefBrowser browser,
+                                          double progress);
+
+/// Called when the MAIN frame fails to load.
+typedef void (*OnLoadErrorCallback)(RustRefBrowser browser,
+                                    int error_code,
+                                    const char* error_text,
+                                    const char* failed_url);
+
 /// Called when the page title changes.
 typedef void (*OnTitleChangeCallback)(RustRefBrowser browser,
                                       const char* title);
@@ -94,6 +122,7 @@ struct FFIRustV8Value {
     bool bool_;
     double double_;
     int32_t int_;
+    /// String, or the JSON of an Array or Object
     RustRefString string;
     uint32_t uint;
   };
@@ -113,16 +142,73 @@ typedef void (*OnJavascriptCallback)(RustRefBrowser browser,
 
 typedef bool (*OnCertificateErrorCallback)(RustRefBrowser browser);
 
+/// Called when the page shows an alert, confirm, prompt or beforeunload
+/// dialog. dialog_type is a cef_jsdialog_type_t, or 3 for beforeunload.
+typedef void (*OnJSDialogCallback)(RustRefBrowser browser,
+                                   RustRefJSDialogCallback callback,
+                                   int dialog_type,
+                                   const char* message_text,
+                                   const char* default_prompt_text);
+
+/// Called when the page runs window.cef.postMessage(value), with value as JSON
+typedef void (*OnPageMessageCallback)(RustRefBrowser browser,
+                                      const char* json);
+
+/// Called when a download starts, returns the full path to save it to, or an
+/// empty string to cancel it. total_bytes is -1 if unknown.
+typedef RustRefString (*OnBeforeDownloadCallback)(RustRefBrowser browser,
+                                                  uint32_t id,
+                                                  const char* url,
+                                                  const char* suggested_name,
+                                                  int64_t total_bytes);
+
+/// Called as a download progresses, return false to cancel it.
+typedef bool (*OnDownloadUpdatedCallback)(RustRefBrowser browser,
+                                          uint32_t id,
+                                          int64_t received_bytes,
+                                          int64_t total_bytes,
+                                          bool is_complete,
+                                          bool is_canceled);
+
+/// Called when the page tries to open a popup, which we always block.
+/// Return true to load target_url in the frame that opened it instead.
+typedef bool (*OnBeforePopupCallback)(RustRefBrowser browser,
+                                      const char* target_url);
+
+/// Called on the IO thread before every request, return true to cancel it.
+typedef bool (*OnBeforeResourceLoadCallback)(const char* url);
+
+/// Called on the IO thread before every request, return false to skip
+/// on_before_resource_load when there's nothing to block.
+typedef bool (*HasBlocklistCallback)();
+
+/// Called before any frame navigates, including redirects. Return false to
+/// cancel it.
+typedef bool (*OnBeforeBrowseCallback)(RustRefBrowser browser,
+                                       const char* url,
+                                       bool is_main_frame);
+
 struct Callbacks {
   OnContextInitializedCallback on_context_initialized;
   OnAfterCreatedCallback on_after_created;
   OnBeforeCloseCallback on_before_close;
   OnPaintCallback on_paint;
+  OnLoadStartCallback on_load_start;
+  OnLoadingProgressCallback on_loading_progress;
+  OnLoadErrorCallback on_load_error;
   OnLoadEndCallback on_load_end;
   OnTitleChangeCallback on_title_change;
   GetViewRectCallback get_view_rect;
   OnJavascriptCallback on_javascript;
   OnCertificateErrorCallback on_certificate_error;
+  OnBeforePopupCallback on_before_popup;
+  OnJSDialogCallback on_js_dialog;
+  OnPageMessageCallback on_page_message;
+  OnBeforeDownloadCallback on_before_download;
+  OnDownloadUpdatedCallback on_download_updated;
+  OnBeforeResourceLoadCallback on_before_resource_load;
+  HasBlocklistCallback has_blocklist;
+  OnBeforeBrowseCallback on_before_browse;
 };
 
 struct CefInitializePaths {
@@ -175,11 +261,48 @@ extern "C" int cef_interface_browser_eval_javascript_on_frame(
 extern "C" int cef_interface_browser_send_click(CefBrowser* browser,
                                                 int x,
                                                 int y);
+extern "C" int cef_interface_browser_send_mouse_move(CefBrowser* browser,
+                                                     int x,
+                                                     int y,
+                                                     uint32_t modifiers,
+                                                     bool mouse_leave);
+/// button is 0 left, 1 middle, 2 right
+extern "C" int cef_interface_browser_send_mouse_button(CefBrowser* browser,
+                                                       int x,
+                                                       int y,
+                                                       uint32_t modifiers,
+                                                       int button,
+                                                       bool mouse_up,
+                                                       int click_count);
+extern "C" int cef_interface_browser_send_mouse_wheel(CefBrowser* browser,
+                                                      int x,
+                                                      int y,
+                                                      uint32_t modifiers,
+                                                      int delta_x,
+                                                      int delta_y);
 extern "C" int cef_interface_browser_send_text(CefBrowser* browser,
                                                const char* text);
+/// type is a cef_key_event_type_t, character is only used for KEYEVENT_CHAR
+extern "C" int cef_interface_browser_send_key(CefBrowser* browser,
+                                              int type,
+                                              uint32_t modifiers,
+                                              int windows_key_code,
+                                              uint16_t character);
 extern "C" int cef_interface_browser_reload(CefBrowser* browser);
+extern "C" int cef_interface_browser_go_back(CefBrowser* browser);
+extern "C" int cef_interface_browser_go_forward(CefBrowser* browser);
+extern "C" bool cef_interface_browser_can_go_back(CefBrowser* browser);
+extern "C" bool cef_interface_browser_can_go_forward(CefBrowser* browser);
+extern "C" int cef_interface_browser_stop_load(CefBrowser* browser);
+/// url of the main frame, must call cef_interface_delete_ref_string
+extern "C" RustRefString cef_interface_browser_get_url(CefBrowser* browser);
 
 extern "C" int cef_interface_browser_was_resized(CefBrowser* browser);
+/// Repaint the whole view even if nothing changed
+extern "C" int cef_interface_browser_invalidate(CefBrowser* browser);
+/// 0.0 is 100%, each step of 1.0 is 20% bigger or smaller
+extern "C" int cef_interface_browser_set_zoom_level(CefBrowser* browser,
+                                                    double zoom_level);
 extern "C" int cef_interface_browser_open_dev_tools(CefBrowser* browser);
 extern "C" int cef_interface_browser_set_audio_muted(CefBrowser* browser,
                                                      bool mute);
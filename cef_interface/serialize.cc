@@ -1,11 +1,47 @@
 #include "serialize.hh"
 
+// JSON.stringify(v) in the entered context, fails for things like circular
+// objects
+bool json_stringify(CefV8Value* v, std::string& out) {
+  auto context = CefV8Context::GetEnteredContext();
+  if (!context) {
+    return false;
+  }
+
+  auto json = context->GetGlobal()->GetValue("JSON");
+  if (!json || !json->IsObject()) {
+    return false;
+  }
+
+  auto stringify = json->GetValue("stringify");
+  if (!stringify || !stringify->IsFunction()) {
+    return false;
+  }
+
+  CefV8ValueList args;
+  args.push_back(v);
+  auto result = stringify->ExecuteFunction(json, args);
+  if (!result || !result->IsString()) {
+    stringify->ClearException();
+    return false;
+  }
+
+  out = result->GetStringValue().ToString();
+  return true;
+}
+
 FFIRustV8Value create_rust_v8_value(CefV8Value* v) {
   FFIRustV8Value rust_value;
   rust_value.tag = FFIRustV8ValueTag::Unknown;
 
+  std::string json;
+
   if (v->IsArray()) {
-    rust_value.tag = FFIRustV8ValueTag::Array;
+    if (json_stringify(v, json)) {
+      rust_value.tag = FFIRustV8ValueTag::Array;
+      rust_value.string =
+          cef_interface_new_ref_string(json.c_str(), json.length());
+    }
   } else if (v->IsArrayBuffer()) {
     rust_value.tag = FFIRustV8ValueTag::ArrayBuffer;
   } else if (v->IsBool()) {
@@ -24,7 +60,11 @@ FFIRustV8Value create_rust_v8_value(CefV8Value* v) {
   } else if (v->IsNull()) {
     rust_value.tag = FFIRustV8ValueTag::Null;
   } else if (v->IsObject()) {
-    rust_value.tag = FFIRustV8ValueTag::Object;
+    if (json_stringify(v, json)) {
+      rust_value.tag = FFIRustV8ValueTag::Object;
+      rust_value.string =
+          cef_interface_new_ref_string(json.c_str(), json.length());
+    }
   } else if (v->IsString()) {
     rust_value.tag = FFIRustV8ValueTag::String;
     std::string s = v->GetStringValue().ToString();
@@ -63,7 +103,9 @@ CefRefPtr<CefBinaryValue> serialize_v8_response(FFIRustV8Response response) {
       write(s, response.result.double_);
     } else if (response.result.tag == FFIRustV8ValueTag::Int) {
       write(s, response.result.int_);
-    } else if (response.result.tag == FFIRustV8ValueTag::String) {
+    } else if (response.result.tag == FFIRustV8ValueTag::String ||
+               response.result.tag == FFIRustV8ValueTag::Array ||
+               response.result.tag == FFIRustV8ValueTag::Object) {
       write(s, response.result.string.len);
       s.write(response.result.string.ptr, response.result.string.len);
     } else if (response.result.tag == FFIRustV8ValueTag::UInt) {
@@ -111,7 +153,9 @@ FFIRustV8Response deserialize_v8_response(CefBinaryValue* binary) {
       read(s, &response.result.double_);
     } else if (response.result.tag == FFIRustV8ValueTag::Int) {
       read(s, &response.result.int_);
-    } else if (response.result.tag == FFIRustV8ValueTag::String) {
+    } else if (response.result.tag == FFIRustV8ValueTag::String ||
+               response.result.tag == FFIRustV8ValueTag::Array ||
+               response.result.tag == FFIRustV8ValueTag::Object) {
       size_t string_len = 0;
       read(s, &string_len);
 
@@ -4,6 +4,7 @@
 
 #include "interface.hh"
 
+bool json_stringify(CefV8Value* v, std::string& out);
 FFIRustV8Value create_rust_v8_value(CefV8Value* v);
 
 CefRefPtr<CefBinaryValue> serialize_v8_response(FFIRustV8Response v8_response);
@@ -1 +1,13 @@
+pub mod oembed;
+pub mod soundcloud;
+pub mod twitch;
 pub mod youtube;
+
+const APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+fn make_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap()
+}
@@ -0,0 +1,125 @@
+//! https://oembed.com lookups for players we can embed
+
+use classicube_helpers::async_manager;
+use regex::Regex;
+use serde::Deserialize;
+use tracing::debug;
+use url::Url;
+
+use super::make_client;
+use crate::error::{Error, Result, ResultExt};
+
+/// (hosts, oEmbed endpoint) of providers whose embeds speak the player.js protocol
+const PROVIDERS: &[(&[&str], &str)] = &[
+    (
+        &["vimeo.com", "www.vimeo.com", "player.vimeo.com"],
+        "https://vimeo.com/api/oembed.json",
+    ),
+    (
+        &["streamable.com", "www.streamable.com"],
+        "https://api.streamable.com/oembed.json",
+    ),
+    (
+        &["wistia.com", "*.wistia.com", "fast.wistia.net", "wi.st"],
+        "https://fast.wistia.com/oembed",
+    ),
+];
+
+/// `pattern` can start with "*." to match subdomains
+fn host_matches(host: &str, pattern: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .map_or(false, |subdomain| subdomain.ends_with('.')),
+        None => host == pattern,
+    }
+}
+
+pub fn find_endpoint(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+
+    PROVIDERS
+        .iter()
+        .find(|(patterns, _)| patterns.iter().any(|pattern| host_matches(host, pattern)))
+        .map(|(_, endpoint)| *endpoint)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OEmbedResponse {
+    pub title: Option<String>,
+    pub provider_name: Option<String>,
+    html: Option<String>,
+}
+
+impl OEmbedResponse {
+    /// src of the iframe in `html`
+    pub fn iframe_src(&self) -> Option<String> {
+        let html = self.html.as_ref()?;
+        let captures = Regex::new(r#"<iframe[^>]*\ssrc="([^"]+)""#)
+            .unwrap()
+            .captures(html)?;
+
+        Some(captures[1].replace("&amp;", "&"))
+    }
+}
+
+pub async fn lookup(endpoint: &str, url: &str) -> Result<OEmbedResponse> {
+    let endpoint = endpoint.to_string();
+    let url = url.to_string();
+
+    let result = async_manager::spawn(async move {
+        let client = make_client();
+        let bytes = client
+            .get(&endpoint)
+            .query(&[("url", &url), ("format", &"json".to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok::<_, Error>(
+            serde_json::from_slice::<OEmbedResponse>(&bytes)
+                .chain_err(|| "couldn't parse oEmbed response")?,
+        )
+    })
+    .await??;
+
+    debug!("{:?}", result);
+
+    Ok(result)
+}
+
+#[test]
+fn test_find_endpoint() {
+    for (url, endpoint) in [
+        ("https://vimeo.com/76979871", Some(PROVIDERS[0].1)),
+        (
+            "https://player.vimeo.com/video/76979871",
+            Some(PROVIDERS[0].1),
+        ),
+        ("https://streamable.com/abcde", Some(PROVIDERS[1].1)),
+        ("https://home.wistia.com/medias/abcde", Some(PROVIDERS[2].1)),
+        ("https://www.classicube.net/", None),
+    ] {
+        assert_eq!(find_endpoint(&Url::parse(url).unwrap()), endpoint, "{url}");
+    }
+}
+
+#[test]
+fn test_iframe_src() {
+    let response: OEmbedResponse = serde_json::from_str(
+        r#"{
+            "type": "video",
+            "title": "The New Vimeo Player (You Know, For Videos)",
+            "provider_name": "Vimeo",
+            "html": "<iframe src=\"https://player.vimeo.com/video/76979871?h=8272103f6e&amp;app_id=122963\" width=\"640\" height=\"360\" frameborder=\"0\"></iframe>"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        response.iframe_src().as_deref(),
+        Some("https://player.vimeo.com/video/76979871?h=8272103f6e&app_id=122963")
+    );
+}
@@ -0,0 +1,71 @@
+use classicube_helpers::async_manager;
+use regex::Regex;
+use tracing::debug;
+
+use super::make_client;
+use crate::error::{Error, Result};
+
+/// Track urls of a set, read from the page's `<noscript>` listing
+/// because the api needs a client id.
+#[tracing::instrument]
+pub async fn playlist(url: &str) -> Result<Vec<String>> {
+    let url = url.to_string();
+
+    let html = async_manager::spawn(async move {
+        let client = make_client();
+        let text = client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok::<_, Error>(text)
+    })
+    .await??;
+
+    let result = parse_playlist(&html);
+    debug!("{:?}", result);
+
+    Ok(result)
+}
+
+fn parse_playlist(html: &str) -> Vec<String> {
+    let regex = Regex::new(r#"itemprop="track"[\s\S]*?itemprop="url" href="(/[^"]+)""#).unwrap();
+
+    let mut urls: Vec<String> = Vec::new();
+    for captures in regex.captures_iter(html) {
+        let url = format!("https://soundcloud.com{}", &captures[1]);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+#[test]
+fn test_parse_playlist() {
+    let html = r#"
+        <noscript><div>
+        <article itemprop="track" itemscope itemtype="http://schema.org/MusicRecording">
+          <h2 itemprop="name"><a itemprop="url" href="/artist/first-track">First</a>
+            by <a href="/artist">Artist</a></h2>
+        </article>
+        <article itemprop="track" itemscope itemtype="http://schema.org/MusicRecording">
+          <h2 itemprop="name"><a itemprop="url" href="/other/second-track">Second</a>
+            by <a href="/other">Other</a></h2>
+        </article>
+        </div></noscript>
+    "#;
+
+    assert_eq!(
+        parse_playlist(html),
+        vec![
+            "https://soundcloud.com/artist/first-track".to_string(),
+            "https://soundcloud.com/other/second-track".to_string(),
+        ]
+    );
+    assert!(parse_playlist("<html></html>").is_empty());
+}
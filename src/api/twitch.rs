@@ -0,0 +1,78 @@
+//! Clip lengths from Twitch's website api, helix needs an oauth token
+
+use std::time::Duration;
+
+use classicube_helpers::async_manager;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::make_client;
+use crate::error::{Error, Result, ResultExt};
+
+const GQL_URL: &str = "https://gql.twitch.tv/gql";
+
+/// the website's public client id
+const CLIENT_ID: &str = "kimne78kx3ncx6brgo4mv6wki5h1ko";
+
+#[derive(Debug, Deserialize)]
+struct GqlResponse {
+    data: GqlData,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlData {
+    clip: Option<GqlClip>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlClip {
+    #[serde(rename = "durationSeconds")]
+    duration_seconds: f32,
+}
+
+#[tracing::instrument]
+pub async fn clip_duration(slug: &str) -> Result<Duration> {
+    let body = serde_json::json!({
+        "query": "query($slug: ID!) { clip(slug: $slug) { durationSeconds } }",
+        "variables": { "slug": slug },
+    })
+    .to_string();
+
+    let result = async_manager::spawn(async move {
+        let client = make_client();
+        let bytes = client
+            .post(GQL_URL)
+            .header("Client-Id", CLIENT_ID)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok::<_, Error>(parse_clip_duration(&bytes)?)
+    })
+    .await??;
+
+    debug!("{:?}", result);
+
+    Ok(result)
+}
+
+fn parse_clip_duration(bytes: &[u8]) -> Result<Duration> {
+    let response: GqlResponse =
+        serde_json::from_slice(bytes).chain_err(|| "couldn't parse twitch response")?;
+    let clip = response.data.clip.chain_err(|| "no such clip")?;
+
+    Duration::try_from_secs_f32(clip.duration_seconds).chain_err(|| "bad clip duration")
+}
+
+#[test]
+fn test_parse_clip_duration() {
+    assert_eq!(
+        parse_clip_duration(br#"{"data":{"clip":{"durationSeconds":29.5}}}"#).unwrap(),
+        Duration::from_secs_f32(29.5)
+    );
+    assert!(parse_clip_duration(br#"{"data":{"clip":null}}"#).is_err());
+    assert!(parse_clip_duration(b"not json").is_err());
+}
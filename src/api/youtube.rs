@@ -2,10 +2,10 @@ use classicube_helpers::async_manager;
 use serde::Deserialize;
 use tracing::debug;
 
+use super::make_client;
 use crate::error::{bail, Error, Result};
 
 const API_URL: &str = "https://youtube-api.spiralp.xyz";
-const APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
 #[derive(Debug, Deserialize)]
 struct ApiError {
@@ -20,13 +20,6 @@ pub struct VideoResponse {
     pub duration_seconds: u64,
 }
 
-fn make_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent(APP_USER_AGENT)
-        .build()
-        .unwrap()
-}
-
 pub async fn video(id: &str) -> Result<VideoResponse> {
     let id = id.to_string();
 
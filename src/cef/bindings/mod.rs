@@ -8,6 +8,7 @@ use std::{
     ptr, slice,
 };
 
+use serde::de::DeserializeOwned;
 use tracing::{debug, warn};
 use url::Url;
 
@@ -31,14 +32,29 @@ pub unsafe extern "C" fn rust_warn(c_str: *const ::std::os::raw::c_char) {
 
 const YOUTUBE_HTML: &[u8] = include_bytes!("../../player/youtube/page.html");
 const MEDIA_HTML: &[u8] = include_bytes!("../../player/media/page.html");
+const TWITCH_HTML: &[u8] = include_bytes!("../../player/twitch/page.html");
+const EMBED_HTML: &[u8] = include_bytes!("../../player/embed/page.html");
+const SOUNDCLOUD_HTML: &[u8] = include_bytes!("../../player/soundcloud/page.html");
+
+/// Twitch's embeds need a real parent domain, `local://twitch` doesn't have one
+pub const TWITCH_PAGE_URL: &str = "https://localhost/cef-twitch/";
 
 fn handle_scheme_create(
     _browser: RustRefBrowser,
-    _scheme_name: *const ::std::os::raw::c_char,
+    scheme_name: *const ::std::os::raw::c_char,
     url: *const ::std::os::raw::c_char,
 ) -> Result<&'static [u8]> {
-    // let scheme_name = unsafe { CStr::from_ptr(scheme_name) }.to_str()?;
+    let scheme_name = unsafe { CStr::from_ptr(scheme_name) }.to_str()?;
     let url = unsafe { CStr::from_ptr(url) }.to_str()?;
+
+    // anything else on localhost goes to the network as usual
+    if scheme_name == "https" {
+        if url.starts_with(TWITCH_PAGE_URL) {
+            return Ok(TWITCH_HTML);
+        }
+        bail!("not one of our pages {:?}", url);
+    }
+
     let url = Url::parse(url)?;
     let host = url.host_str().chain_err(|| "no host part on url")?;
 
@@ -47,6 +63,8 @@ fn handle_scheme_create(
     match host {
         "youtube" => Ok(YOUTUBE_HTML),
         "media" => Ok(MEDIA_HTML),
+        "embed" => Ok(EMBED_HTML),
+        "soundcloud" => Ok(SOUNDCLOUD_HTML),
 
         _ => {
             bail!("no such local scheme for {:?}", host);
@@ -271,6 +289,17 @@ impl RustRefBrowser {
         }
     }
 
+    /// Like `eval_javascript`, but deserializes the result from its JSON form
+    #[allow(dead_code)]
+    pub async fn eval_javascript_as<T, C>(&self, code: C) -> Result<T>
+    where
+        T: DeserializeOwned,
+        C: Into<Vec<u8>>,
+    {
+        let v8_value = self.eval_javascript(code).await?;
+        Ok(serde_json::from_value(v8_value.into())?)
+    }
+
     #[allow(dead_code)]
     pub async fn eval_javascript_on_frame<T: Into<Vec<u8>>, U: Into<Vec<u8>>>(
         &self,
@@ -307,19 +336,125 @@ impl RustRefBrowser {
         to_result(unsafe { cef_interface_browser_send_click(self.ptr, x, y) })
     }
 
+    pub fn send_mouse_move(
+        &self,
+        x: c_int,
+        y: c_int,
+        modifiers: u32,
+        mouse_leave: bool,
+    ) -> Result<()> {
+        to_result(unsafe {
+            cef_interface_browser_send_mouse_move(self.ptr, x, y, modifiers, mouse_leave)
+        })
+    }
+
+    /// button is 0 left, 1 middle, 2 right
+    pub fn send_mouse_button(
+        &self,
+        x: c_int,
+        y: c_int,
+        modifiers: u32,
+        button: c_int,
+        mouse_up: bool,
+        click_count: c_int,
+    ) -> Result<()> {
+        to_result(unsafe {
+            cef_interface_browser_send_mouse_button(
+                self.ptr,
+                x,
+                y,
+                modifiers,
+                button,
+                mouse_up,
+                click_count,
+            )
+        })
+    }
+
+    pub fn send_mouse_wheel(
+        &self,
+        x: c_int,
+        y: c_int,
+        modifiers: u32,
+        delta_x: c_int,
+        delta_y: c_int,
+    ) -> Result<()> {
+        to_result(unsafe {
+            cef_interface_browser_send_mouse_wheel(self.ptr, x, y, modifiers, delta_x, delta_y)
+        })
+    }
+
     pub fn send_text<T: Into<Vec<u8>>>(&self, text: T) -> Result<()> {
         let text = CString::new(text)?;
         to_result(unsafe { cef_interface_browser_send_text(self.ptr, text.as_ptr()) })
     }
 
+    /// `key_type` is a `cef_key_event_type_t`, character is only used for char events
+    pub fn send_key(
+        &self,
+        key_type: c_int,
+        modifiers: u32,
+        windows_key_code: c_int,
+        character: u16,
+    ) -> Result<()> {
+        to_result(unsafe {
+            cef_interface_browser_send_key(
+                self.ptr,
+                key_type,
+                modifiers,
+                windows_key_code,
+                character,
+            )
+        })
+    }
+
     pub fn reload(&self) -> Result<()> {
         to_result(unsafe { cef_interface_browser_reload(self.ptr) })
     }
 
+    pub fn go_back(&self) -> Result<()> {
+        to_result(unsafe { cef_interface_browser_go_back(self.ptr) })
+    }
+
+    pub fn go_forward(&self) -> Result<()> {
+        to_result(unsafe { cef_interface_browser_go_forward(self.ptr) })
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        unsafe { cef_interface_browser_can_go_back(self.ptr) }
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        unsafe { cef_interface_browser_can_go_forward(self.ptr) }
+    }
+
+    pub fn stop_load(&self) -> Result<()> {
+        to_result(unsafe { cef_interface_browser_stop_load(self.ptr) })
+    }
+
+    /// url of the main frame, which changes as the page navigates
+    pub fn get_url(&self) -> String {
+        let url = unsafe { cef_interface_browser_get_url(self.ptr) };
+        url.to_string()
+    }
+
     pub fn was_resized(&self) -> Result<()> {
         to_result(unsafe { cef_interface_browser_was_resized(self.ptr) })
     }
 
+    /// repaint everything, `on_paint` is only called when something changes
+    pub fn invalidate(&self) -> Result<()> {
+        to_result(unsafe { cef_interface_browser_invalidate(self.ptr) })
+    }
+
+    /// `zoom` of 1.0 is 100%
+    pub fn set_zoom(&self, zoom: f32) -> Result<()> {
+        // chromium zooms in steps of 20%
+        let zoom_level = f64::from(zoom).ln() / 1.2f64.ln();
+
+        to_result(unsafe { cef_interface_browser_set_zoom_level(self.ptr, zoom_level) })
+    }
+
     pub fn open_dev_tools(&self) -> Result<()> {
         to_result(unsafe { cef_interface_browser_open_dev_tools(self.ptr) })
     }
@@ -343,6 +478,27 @@ impl Clone for RustRefBrowser {
     }
 }
 
+impl RustRefJSDialogCallback {
+    /// answer the dialog, only the first call counts
+    pub fn respond<T: Into<Vec<u8>>>(&self, success: bool, user_input: T) -> Result<()> {
+        let user_input = CString::new(user_input)?;
+
+        to_result(unsafe {
+            cef_interface_js_dialog_callback_continue(self.ptr, success, user_input.as_ptr())
+        })
+    }
+}
+impl Drop for RustRefJSDialogCallback {
+    fn drop(&mut self) {
+        to_result(unsafe { cef_interface_release_ref_js_dialog_callback(self.ptr) }).unwrap();
+    }
+}
+impl Clone for RustRefJSDialogCallback {
+    fn clone(&self) -> Self {
+        unsafe { cef_interface_add_ref_js_dialog_callback(self.ptr) }
+    }
+}
+
 impl ToString for RustRefString {
     fn to_string(&self) -> String {
         let s = unsafe { slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) };
@@ -369,7 +525,9 @@ impl FFIRustV8Value {
         unsafe {
             match self.tag {
                 FFIRustV8ValueTag::Unknown => RustV8Value::Unknown,
-                FFIRustV8ValueTag::Array => RustV8Value::Array,
+                FFIRustV8ValueTag::Array => {
+                    RustV8Value::from_json(&inner.string.as_ref().to_string())
+                }
                 FFIRustV8ValueTag::ArrayBuffer => RustV8Value::ArrayBuffer,
                 FFIRustV8ValueTag::Bool => RustV8Value::Bool(*inner.bool_.as_ref()),
                 FFIRustV8ValueTag::Date => RustV8Value::Date,
@@ -377,7 +535,9 @@ impl FFIRustV8Value {
                 FFIRustV8ValueTag::Function => RustV8Value::Function,
                 FFIRustV8ValueTag::Int => RustV8Value::Int(*inner.int_.as_ref()),
                 FFIRustV8ValueTag::Null => RustV8Value::Null,
-                FFIRustV8ValueTag::Object => RustV8Value::Object,
+                FFIRustV8ValueTag::Object => {
+                    RustV8Value::from_json(&inner.string.as_ref().to_string())
+                }
                 FFIRustV8ValueTag::String => RustV8Value::String(inner.string.as_ref().to_string()),
                 FFIRustV8ValueTag::UInt => RustV8Value::UInt(*inner.uint.as_ref()),
                 FFIRustV8ValueTag::Undefined => RustV8Value::Undefined,
@@ -395,15 +555,15 @@ impl Drop for FFIRustV8Value {
                 FFIRustV8ValueTag::Bool => mem::swap(inner.bool_.as_mut(), &mut mem::zeroed()),
                 FFIRustV8ValueTag::Double => mem::swap(inner.double_.as_mut(), &mut mem::zeroed()),
                 FFIRustV8ValueTag::Int => mem::swap(inner.int_.as_mut(), &mut mem::zeroed()),
-                FFIRustV8ValueTag::String => mem::swap(inner.string.as_mut(), &mut mem::zeroed()),
+                FFIRustV8ValueTag::String
+                | FFIRustV8ValueTag::Array
+                | FFIRustV8ValueTag::Object => mem::swap(inner.string.as_mut(), &mut mem::zeroed()),
                 FFIRustV8ValueTag::UInt => mem::swap(inner.uint.as_mut(), &mut mem::zeroed()),
                 FFIRustV8ValueTag::Unknown
-                | FFIRustV8ValueTag::Array
                 | FFIRustV8ValueTag::ArrayBuffer
                 | FFIRustV8ValueTag::Date
                 | FFIRustV8ValueTag::Function
                 | FFIRustV8ValueTag::Null
-                | FFIRustV8ValueTag::Object
                 | FFIRustV8ValueTag::Undefined => {}
             }
         }
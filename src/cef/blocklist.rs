@@ -0,0 +1,215 @@
+//! Cancels requests to ads and trackers listed in cef/blocklist.txt.
+//!
+//! Understands hosts files (`0.0.0.0 ads.example.com`), plain domains, and
+//! the simple parts of adblock filters (`||ads.example.com^`,
+//! `/banner/*.gif`). Exceptions and element hiding rules are ignored.
+
+use std::{
+    collections::HashSet,
+    ffi::CStr,
+    fs,
+    net::IpAddr,
+    os::raw::c_char,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use classicube_helpers::async_manager;
+use tracing::debug;
+use url::Url;
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    options::BLOCKLIST,
+};
+
+/// read from the IO thread, so it can't be a `thread_local`
+static RULES: RwLock<Option<Blocklist>> = RwLock::new(None);
+
+#[derive(Debug, Default)]
+struct Blocklist {
+    /// blocks these and their subdomains
+    domains: HashSet<String>,
+    /// `*` separated parts found in order anywhere in the url
+    patterns: Vec<Vec<String>>,
+}
+
+impl Blocklist {
+    fn parse(text: &str) -> Self {
+        let mut blocklist = Self::default();
+
+        for line in text.lines() {
+            let line = line.trim().to_lowercase();
+            if line.is_empty()
+                || line.starts_with(['!', '#', '['])
+                || line.starts_with("@@")
+                || line.contains("##")
+                || line.contains("#@#")
+            {
+                continue;
+            }
+
+            // hosts file
+            let mut words = line.split_whitespace();
+            if let Some(first) = words.next() {
+                if first.parse::<IpAddr>().is_ok() {
+                    for domain in words.take_while(|word| !word.starts_with('#')) {
+                        if domain != "localhost" && domain.parse::<IpAddr>().is_err() {
+                            blocklist.domains.insert(domain.to_string());
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            // drop options like $third-party
+            let rule = line.split('$').next().unwrap_or_default();
+            let anchored = rule.strip_prefix("||");
+            let domain = anchored.unwrap_or(rule).trim_end_matches('^');
+            if domain.contains('.')
+                && domain
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+            {
+                blocklist.domains.insert(domain.to_string());
+                continue;
+            }
+
+            let parts: Vec<String> = rule
+                .trim_start_matches('|')
+                .trim_end_matches('|')
+                .split(['*', '^'])
+                .filter(|part| !part.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            // would match everything
+            if !parts.is_empty() {
+                blocklist.patterns.push(parts);
+            }
+        }
+
+        blocklist
+    }
+
+    fn len(&self) -> usize {
+        self.domains.len() + self.patterns.len()
+    }
+
+    fn is_blocked(&self, url: &str) -> bool {
+        let url = url.to_lowercase();
+
+        if let Some(host) = Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(ToString::to_string))
+        {
+            // ads.example.com, example.com, com
+            let mut domain = host.as_str();
+            loop {
+                if self.domains.contains(domain) {
+                    return true;
+                }
+                match domain.split_once('.') {
+                    Some((_, parent)) => domain = parent,
+                    None => break,
+                }
+            }
+        }
+
+        self.patterns.iter().any(|parts| {
+            let mut rest = url.as_str();
+            parts.iter().all(|part| {
+                rest.find(part.as_str()).is_some_and(|index| {
+                    rest = &rest[index + part.len()..];
+                    true
+                })
+            })
+        })
+    }
+}
+
+fn path() -> PathBuf {
+    Path::new("cef").join("blocklist.txt")
+}
+
+/// Reads cef/blocklist.txt again if the blocklist is enabled, returns how
+/// many rules it has, or None if it's disabled or there's no file.
+pub async fn reload() -> Result<Option<usize>> {
+    let blocklist = if BLOCKLIST.get()? {
+        async_manager::spawn(async {
+            let path = path();
+            if !path.is_file() {
+                return Ok(None);
+            }
+
+            let text = fs::read_to_string(&path)
+                .chain_err(|| format!("couldn't read {}", path.display()))?;
+            Ok::<_, Error>(Some(Blocklist::parse(&text)))
+        })
+        .await??
+    } else {
+        None
+    };
+
+    let len = blocklist.as_ref().map(Blocklist::len);
+    debug!("blocklist reloaded with {:?} rules", len);
+    // so requests aren't checked against nothing
+    let blocklist = blocklist.filter(|blocklist| blocklist.len() != 0);
+
+    if let Ok(mut rules) = RULES.write() {
+        *rules = blocklist;
+    }
+
+    Ok(len)
+}
+
+pub fn display_path() -> String {
+    path().display().to_string()
+}
+
+/// Returns true if there are rules to check requests against.
+pub extern "C" fn has_blocklist_callback() -> bool {
+    RULES.read().is_ok_and(|rules| rules.is_some())
+}
+
+/// Returns true to cancel the request.
+pub extern "C" fn on_before_resource_load_callback(url_c_str: *const c_char) -> bool {
+    let Ok(rules) = RULES.read() else {
+        return false;
+    };
+    let Some(blocklist) = rules.as_ref() else {
+        return false;
+    };
+
+    let url = unsafe { CStr::from_ptr(url_c_str) }.to_string_lossy();
+    let blocked = blocklist.is_blocked(&url);
+    if blocked {
+        debug!("blocked {}", url);
+    }
+    blocked
+}
+
+#[test]
+fn test_blocklist() {
+    let blocklist = Blocklist::parse(
+        "! comment\n[Adblock Plus 2.0]\n# hosts comment\n0.0.0.0 ads.example.com\n127.0.0.1 \
+         localhost\n::1 tracker.example.net # inline \
+         comment\ndoubleclick.net\n||AdServer.example.org^$third-party\n/banner/*.gif\n@@\
+         ||allowed.example.com^\nexample.com##.ad-box\n*\n",
+    );
+    assert_eq!(blocklist.domains.len(), 4);
+    assert_eq!(blocklist.patterns.len(), 1);
+
+    assert!(blocklist.is_blocked("https://ads.example.com/script.js"));
+    assert!(blocklist.is_blocked("https://static.ads.example.com/a.png"));
+    assert!(blocklist.is_blocked("http://tracker.example.net/"));
+    assert!(blocklist.is_blocked("https://securepubads.g.doubleclick.net/tag/js/gpt.js"));
+    assert!(blocklist.is_blocked("https://adserver.example.org/ad"));
+    assert!(blocklist.is_blocked("https://cdn.site.com/img/banner/top.gif"));
+
+    assert!(!blocklist.is_blocked("https://example.com/"));
+    assert!(!blocklist.is_blocked("https://notads.example.com.evil.org/"));
+    assert!(!blocklist.is_blocked("https://www.youtube.com/embed/abc"));
+    assert!(!blocklist.is_blocked("https://cdn.site.com/img/banner/top.png"));
+    assert!(!blocklist.is_blocked("data:text/html,hello"));
+    assert!(!blocklist.is_blocked("http://localhost:8080/"));
+}
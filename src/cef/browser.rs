@@ -2,14 +2,17 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     ffi::CStr,
-    os::raw::{c_char, c_int},
+    os::raw::{c_char, c_double, c_int},
 };
 
 use classicube_helpers::WithInner;
 use tracing::{debug, warn};
 
-use super::{bindings::RustRect, CefEvent, CEF_DEFAULT_HEIGHT, CEF_DEFAULT_WIDTH, EVENT_QUEUE};
-use crate::cef::RustRefBrowser;
+use super::{
+    bindings::RustRect, dialog, CefEvent, LoadError, PopupPolicy, CEF_DEFAULT_HEIGHT,
+    CEF_DEFAULT_WIDTH, EVENT_QUEUE,
+};
+use crate::{cef::RustRefBrowser, options::POPUPS, player::url_policy};
 
 // identifier, browser
 thread_local!(
@@ -51,6 +54,8 @@ pub extern "C" fn on_before_close(browser: RustRefBrowser) {
     let id = browser.get_identifier();
     debug!("on_before_close {}", id);
 
+    dialog::on_browser_close(id);
+
     EVENT_QUEUE
         .with_inner_mut(move |(sender, _receiver)| {
             let _ignore_error = sender.send(CefEvent::BrowserClosed(browser));
@@ -76,6 +81,59 @@ pub extern "C" fn on_page_loaded(browser: RustRefBrowser) {
         .unwrap();
 }
 
+// OnLoadStart
+#[tracing::instrument(fields(browser = browser.get_identifier()))]
+pub extern "C" fn on_load_start(browser: RustRefBrowser) {
+    let id = browser.get_identifier();
+    debug!("on_load_start {}", id);
+
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::BrowserLoadStart(browser));
+        })
+        .unwrap();
+}
+
+// OnLoadingProgressChange
+#[tracing::instrument(fields(browser = browser.get_identifier()))]
+pub extern "C" fn on_loading_progress(browser: RustRefBrowser, progress: c_double) {
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::BrowserLoadProgress(browser, progress));
+        })
+        .unwrap();
+}
+
+// OnLoadError
+#[tracing::instrument(fields(browser = browser.get_identifier(), error_code))]
+pub extern "C" fn on_load_error(
+    browser: RustRefBrowser,
+    error_code: c_int,
+    error_text_c_str: *const c_char,
+    failed_url_c_str: *const c_char,
+) {
+    let id = browser.get_identifier();
+    let text = unsafe { CStr::from_ptr(error_text_c_str) }
+        .to_string_lossy()
+        .to_string();
+    let url = unsafe { CStr::from_ptr(failed_url_c_str) }
+        .to_string_lossy()
+        .to_string();
+    warn!("on_load_error {} {} {} {}", id, error_code, text, url);
+
+    let error = LoadError {
+        code: error_code,
+        text,
+        url,
+    };
+
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::BrowserLoadError(browser, error));
+        })
+        .unwrap();
+}
+
 // OnTitleChange
 #[tracing::instrument(fields(browser = browser.get_identifier(), title_c_str))]
 pub extern "C" fn on_title_change(browser: RustRefBrowser, title_c_str: *const c_char) {
@@ -92,6 +150,67 @@ pub extern "C" fn on_title_change(browser: RustRefBrowser, title_c_str: *const c
         .unwrap();
 }
 
+// window.cef.postMessage
+#[tracing::instrument(fields(browser = browser.get_identifier(), json_c_str))]
+pub extern "C" fn on_page_message(browser: RustRefBrowser, json_c_str: *const c_char) {
+    let id = browser.get_identifier();
+    let json = unsafe { CStr::from_ptr(json_c_str) }.to_string_lossy();
+    debug!("on_page_message {} {}", id, json);
+
+    let message = match serde_json::from_str(&json) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("page message from browser {}: {}", id, e);
+            return;
+        }
+    };
+
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::PageMessage(browser, message));
+        })
+        .unwrap();
+}
+
+// OnBeforeBrowse, iframes too so pages can't embed what the policy blocks
+#[tracing::instrument(fields(browser = browser.get_identifier(), url_c_str, is_main_frame))]
+pub extern "C" fn on_before_browse_callback(
+    browser: RustRefBrowser,
+    url_c_str: *const c_char,
+    is_main_frame: bool,
+) -> bool {
+    let url = unsafe { CStr::from_ptr(url_c_str) }
+        .to_string_lossy()
+        .to_string();
+
+    match url_policy::check(&url) {
+        Ok(()) => true,
+
+        Err(e) => {
+            warn!(
+                "browser {} navigating to {:?}: {}",
+                browser.get_identifier(),
+                url,
+                e
+            );
+
+            // pages can have lots of iframes, only tell about the page itself
+            if !is_main_frame {
+                return false;
+            }
+
+            EVENT_QUEUE
+                .with_inner_mut(move |(sender, _receiver)| {
+                    let _ignore_error =
+                        sender.send(CefEvent::BrowserNavigationBlocked(browser, e.to_string()));
+                })
+                .unwrap();
+
+            false
+        }
+    }
+}
+
 #[tracing::instrument(fields(browser = browser.get_identifier()))]
 pub extern "C" fn get_view_rect(browser: RustRefBrowser) -> RustRect {
     let browser_id = browser.get_identifier();
@@ -126,3 +245,37 @@ pub extern "C" fn on_certificate_error_callback(browser: RustRefBrowser) -> bool
             .map_or(false, |allow| *allow)
     })
 }
+
+#[tracing::instrument(fields(browser = browser.get_identifier(), target_url_c_str))]
+pub extern "C" fn on_before_popup_callback(
+    browser: RustRefBrowser,
+    target_url_c_str: *const c_char,
+) -> bool {
+    let target_url = unsafe { CStr::from_ptr(target_url_c_str) }
+        .to_string_lossy()
+        .to_string();
+
+    let policy = POPUPS.get().unwrap_or_default();
+    debug!(
+        "popup {:?} from browser {}: {}",
+        target_url,
+        browser.get_identifier(),
+        policy
+    );
+
+    match policy {
+        PopupPolicy::Block => false,
+
+        PopupPolicy::Same => true,
+
+        PopupPolicy::Screen => {
+            EVENT_QUEUE
+                .with_inner_mut(move |(sender, _receiver)| {
+                    let _ignore_error = sender.send(CefEvent::BrowserPopup(browser, target_url));
+                })
+                .unwrap();
+
+            false
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! alert, confirm, prompt and beforeunload dialogs wait here until someone
+//! answers them with "cef dialog", or they time out
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CStr,
+    fmt,
+    os::raw::{c_char, c_int},
+    time::Duration,
+};
+
+use classicube_helpers::{async_manager, WithInner};
+use futures::{future::RemoteHandle, prelude::*};
+use tracing::{debug, warn};
+
+use super::{bindings::RustRefJSDialogCallback, CefEvent, RustRefBrowser, EVENT_QUEUE};
+use crate::error::{Result, ResultExt};
+
+/// dismissed if nobody answers by then
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+// browser identifier, dialog
+thread_local!(
+    static PENDING: RefCell<HashMap<c_int, PendingDialog>> = RefCell::default();
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsDialogKind {
+    Alert,
+    Confirm,
+    Prompt,
+    BeforeUnload,
+}
+
+impl JsDialogKind {
+    /// `cef_jsdialog_type_t`, or 3 for beforeunload
+    fn from_ffi(dialog_type: c_int) -> Option<Self> {
+        match dialog_type {
+            0 => Some(Self::Alert),
+            1 => Some(Self::Confirm),
+            2 => Some(Self::Prompt),
+            3 => Some(Self::BeforeUnload),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for JsDialogKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Alert => "alert",
+            Self::Confirm => "confirm",
+            Self::Prompt => "prompt",
+            Self::BeforeUnload => "leave page",
+        };
+        write!(f, "{name}")
+    }
+}
+
+struct PendingDialog {
+    kind: JsDialogKind,
+    default_prompt_text: String,
+
+    /// taken when answered
+    callback: Option<RustRefJSDialogCallback>,
+
+    _timeout_handle: RemoteHandle<()>,
+}
+
+impl Drop for PendingDialog {
+    fn drop(&mut self) {
+        // the page is stuck until it's answered
+        if let Some(callback) = self.callback.take() {
+            let _ignore = callback.respond(false, "");
+        }
+    }
+}
+
+#[tracing::instrument(fields(browser = browser.get_identifier(), dialog_type))]
+pub extern "C" fn on_js_dialog_callback(
+    browser: RustRefBrowser,
+    callback: RustRefJSDialogCallback,
+    dialog_type: c_int,
+    message_text_c_str: *const c_char,
+    default_prompt_text_c_str: *const c_char,
+) {
+    let browser_id = browser.get_identifier();
+    let message = unsafe { CStr::from_ptr(message_text_c_str) }
+        .to_string_lossy()
+        .to_string();
+    let default_prompt_text = unsafe { CStr::from_ptr(default_prompt_text_c_str) }
+        .to_string_lossy()
+        .to_string();
+
+    let Some(kind) = JsDialogKind::from_ffi(dialog_type) else {
+        warn!("unknown dialog type {}", dialog_type);
+        let _ignore = callback.respond(false, "");
+        return;
+    };
+    debug!("on_js_dialog {} {} {:?}", browser_id, kind, message);
+
+    let (f, timeout_handle) = async move {
+        async_manager::sleep(TIMEOUT).await;
+
+        debug!("dialog for browser {} timed out", browser_id);
+        let _ignore = respond(browser_id, false, None);
+    }
+    .remote_handle();
+    async_manager::spawn_local_on_main_thread(f);
+
+    // a replaced dialog gets dismissed when dropped, outside of the borrow
+    let _replaced = PENDING.with(|cell| {
+        let pending = &mut *cell.borrow_mut();
+        pending.insert(
+            browser_id,
+            PendingDialog {
+                kind,
+                default_prompt_text,
+                callback: Some(callback),
+                _timeout_handle: timeout_handle,
+            },
+        )
+    });
+
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::BrowserJsDialog(browser, kind, message));
+        })
+        .unwrap();
+}
+
+/// Answer the dialog waiting on this browser.
+///
+/// `user_input` is only used by prompts, which fall back to their default text.
+pub fn respond(browser_id: c_int, accept: bool, user_input: Option<String>) -> Result<()> {
+    let mut dialog = PENDING
+        .with(|cell| cell.borrow_mut().remove(&browser_id))
+        .chain_err(|| "no dialog waiting on this screen")?;
+
+    let user_input = if dialog.kind == JsDialogKind::Prompt {
+        user_input.unwrap_or_else(|| dialog.default_prompt_text.clone())
+    } else {
+        String::new()
+    };
+
+    let callback = dialog
+        .callback
+        .take()
+        .chain_err(|| "dialog was already answered")?;
+    callback.respond(accept, user_input)?;
+
+    Ok(())
+}
+
+pub fn on_browser_close(browser_id: c_int) {
+    let _removed = PENDING.with(|cell| {
+        let pending = &mut *cell.borrow_mut();
+        pending.remove(&browser_id)
+    });
+}
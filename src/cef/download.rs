@@ -0,0 +1,330 @@
+//! Saves files pages download to cef/downloads, if downloads are enabled and
+//! the file type is allowed.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    ffi::{CStr, CString},
+    fs,
+    os::raw::c_char,
+    path::{Path, PathBuf},
+};
+
+use classicube_helpers::WithInner;
+use tracing::{debug, warn};
+
+use super::{
+    bindings::{cef_interface_new_ref_string, RustRefString},
+    CefEvent, RustRefBrowser, EVENT_QUEUE,
+};
+use crate::{
+    error::{bail, ensure, Result, ResultExt},
+    options::{DOWNLOADS, DOWNLOAD_MAX_MB},
+};
+
+const ALLOWED_EXTENSIONS: &[&str] = &[
+    "gif", "jpeg", "jpg", "png", "webp", // images
+    "flac", "mp3", "ogg", "wav", // audio
+    "mp4", "webm", // video
+    "pdf", "txt",
+];
+
+/// downloads saving at once
+const MAX_CONCURRENT: usize = 3;
+
+/// the whole downloads folder, delete old files to make room
+const FOLDER_MAX_MB: u64 = 1024;
+
+// download id, download
+thread_local!(
+    static DOWNLOADS_IN_PROGRESS: RefCell<HashMap<u32, Download>> = RefCell::default();
+);
+
+struct Download {
+    file_name: String,
+    path: PathBuf,
+    percent: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DownloadState {
+    Started,
+    /// from 0 to 100
+    Progress(u8),
+    Complete(PathBuf),
+    Canceled(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadEvent {
+    pub file_name: String,
+    pub state: DownloadState,
+}
+
+fn send_event(browser: RustRefBrowser, file_name: String, state: DownloadState) {
+    EVENT_QUEUE
+        .with_inner_mut(move |(sender, _receiver)| {
+            let _ignore_error = sender.send(CefEvent::BrowserDownload(
+                browser,
+                DownloadEvent { file_name, state },
+            ));
+        })
+        .unwrap();
+}
+
+#[tracing::instrument(fields(browser = browser.get_identifier(), id, total_bytes))]
+pub extern "C" fn on_before_download_callback(
+    browser: RustRefBrowser,
+    id: u32,
+    url_c_str: *const c_char,
+    suggested_name_c_str: *const c_char,
+    total_bytes: i64,
+) -> RustRefString {
+    let url = unsafe { CStr::from_ptr(url_c_str) }.to_string_lossy();
+    let suggested_name = unsafe { CStr::from_ptr(suggested_name_c_str) }
+        .to_string_lossy()
+        .to_string();
+    debug!("on_before_download {} {:?} {:?}", id, url, suggested_name);
+
+    match start(&suggested_name, total_bytes) {
+        Ok((file_name, path)) => {
+            let path_string = path.to_string_lossy().to_string();
+            DOWNLOADS_IN_PROGRESS.with(|cell| {
+                let downloads = &mut *cell.borrow_mut();
+                downloads.insert(
+                    id,
+                    Download {
+                        file_name: file_name.clone(),
+                        path,
+                        percent: None,
+                    },
+                );
+            });
+            send_event(browser, file_name, DownloadState::Started);
+
+            new_ref_string(&path_string)
+        }
+
+        Err(e) => {
+            warn!("download {:?}: {}", suggested_name, e);
+            send_event(
+                browser,
+                suggested_name,
+                DownloadState::Canceled(e.to_string()),
+            );
+
+            // empty cancels it
+            new_ref_string("")
+        }
+    }
+}
+
+/// Returns false to cancel the download.
+#[tracing::instrument(fields(browser = browser.get_identifier(), id))]
+pub extern "C" fn on_download_updated_callback(
+    browser: RustRefBrowser,
+    id: u32,
+    received_bytes: i64,
+    total_bytes: i64,
+    is_complete: bool,
+    is_canceled: bool,
+) -> bool {
+    DOWNLOADS_IN_PROGRESS.with(|cell| {
+        let downloads = &mut *cell.borrow_mut();
+
+        // cef also calls this before on_before_download, it isn't saved
+        // anywhere until we give it a path
+        let Some(download) = downloads.get_mut(&id) else {
+            return true;
+        };
+
+        let max_bytes = max_bytes();
+        let state = if is_canceled {
+            Some(DownloadState::Canceled("canceled".to_string()))
+        } else if received_bytes > max_bytes {
+            // total_bytes can be unknown when it starts
+            Some(DownloadState::Canceled(format!(
+                "bigger than {} MB",
+                DOWNLOAD_MAX_MB.get().unwrap_or_default()
+            )))
+        } else if is_complete {
+            Some(DownloadState::Complete(download.path.clone()))
+        } else {
+            None
+        };
+
+        if let Some(state) = state {
+            let download = downloads.remove(&id).unwrap();
+            let keep_going = matches!(state, DownloadState::Complete(_));
+            send_event(browser, download.file_name, state);
+            return keep_going;
+        }
+
+        if total_bytes > 0 {
+            let percent = u8::try_from(received_bytes * 100 / total_bytes).unwrap_or(100);
+            if download.percent != Some(percent) {
+                download.percent = Some(percent);
+                send_event(
+                    browser,
+                    download.file_name.clone(),
+                    DownloadState::Progress(percent),
+                );
+            }
+        }
+
+        true
+    })
+}
+
+/// `StartDownload` in client.cc deletes it
+fn new_ref_string(s: &str) -> RustRefString {
+    let c_string = CString::new(s).unwrap_or_default();
+    let bytes = c_string.as_bytes();
+    unsafe { cef_interface_new_ref_string(c_string.as_ptr(), bytes.len()) }
+}
+
+fn max_bytes() -> i64 {
+    i64::from(DOWNLOAD_MAX_MB.get().unwrap_or_default()) * 1024 * 1024
+}
+
+/// Returns the file name and where to save it.
+fn start(suggested_name: &str, total_bytes: i64) -> Result<(String, PathBuf)> {
+    ensure!(
+        DOWNLOADS.get()?,
+        "downloads are disabled, enable them with cef config downloads true"
+    );
+
+    let file_name = safe_file_name(suggested_name)?;
+
+    ensure!(
+        total_bytes <= max_bytes(),
+        "bigger than {} MB",
+        DOWNLOAD_MAX_MB.get()?
+    );
+
+    let in_progress = DOWNLOADS_IN_PROGRESS.with(|cell| {
+        cell.borrow()
+            .values()
+            .map(|download| download.path.clone())
+            .collect::<Vec<_>>()
+    });
+    ensure!(
+        in_progress.len() < MAX_CONCURRENT,
+        "already saving {} downloads",
+        in_progress.len()
+    );
+
+    let dir = env::current_dir()
+        .chain_err(|| "current_dir() None")?
+        .join("cef")
+        .join("downloads");
+    fs::create_dir_all(&dir)?;
+
+    // the ones in progress could grow up to the limit
+    let reserved = in_progress.len() as u64 * max_bytes().unsigned_abs();
+    let needed = folder_bytes(&dir)? + reserved + total_bytes.max(0).unsigned_abs();
+    ensure!(
+        needed <= FOLDER_MAX_MB * 1024 * 1024,
+        "downloads folder is full ({} MB), delete some files from {}",
+        FOLDER_MAX_MB,
+        dir.display()
+    );
+
+    let path = unique_path(&dir, &file_name, &in_progress);
+
+    Ok((file_name, path))
+}
+
+/// Only the last path component, with an allowed extension.
+fn safe_file_name(suggested_name: &str) -> Result<String> {
+    let file_name = Path::new(suggested_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let file_name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // no hidden files
+    let file_name = file_name.trim_start_matches('.').trim().to_string();
+
+    let extension = Path::new(&file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .chain_err(|| format!("{suggested_name:?} has no file extension"))?;
+    if !ALLOWED_EXTENSIONS.contains(&extension.as_str()) {
+        bail!(".{} files aren't allowed", extension);
+    }
+
+    Ok(file_name)
+}
+
+fn folder_bytes(dir: &Path) -> Result<u64> {
+    let mut bytes = 0;
+    for entry in fs::read_dir(dir)? {
+        let metadata = entry?.metadata()?;
+        if metadata.is_file() {
+            bytes += metadata.len();
+        }
+    }
+    Ok(bytes)
+}
+
+/// "name (2).ext" if "name.ext" already exists or another download is saving there
+fn unique_path(dir: &Path, file_name: &str, in_progress: &[PathBuf]) -> PathBuf {
+    let is_free = |path: &PathBuf| !path.exists() && !in_progress.contains(path);
+
+    let path = dir.join(file_name);
+    if is_free(&path) {
+        return path;
+    }
+
+    let file_path = Path::new(file_name);
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| dir.join(format!("{stem} ({n}).{extension}")))
+        .find(is_free)
+        .unwrap()
+}
+
+#[test]
+fn test_safe_file_name() {
+    assert_eq!(safe_file_name("cat.png").unwrap(), "cat.png");
+    assert_eq!(safe_file_name("Song Name.MP3").unwrap(), "Song Name.MP3");
+    assert_eq!(safe_file_name("../../evil.png").unwrap(), "evil.png");
+    assert_eq!(safe_file_name(".hidden.txt").unwrap(), "hidden.txt");
+    assert_eq!(safe_file_name("a:b?.jpg").unwrap(), "a_b_.jpg");
+
+    assert!(safe_file_name("setup.exe").is_err());
+    assert!(safe_file_name("script.png.bat").is_err());
+    assert!(safe_file_name("noextension").is_err());
+    assert!(safe_file_name("").is_err());
+}
+
+#[test]
+fn test_unique_path() {
+    let dir = Path::new("does-not-exist");
+    assert_eq!(unique_path(dir, "cat.png", &[]), dir.join("cat.png"));
+
+    let in_progress = [dir.join("cat.png"), dir.join("cat (2).png")];
+    assert_eq!(
+        unique_path(dir, "cat.png", &in_progress),
+        dir.join("cat (3).png")
+    );
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use futures::channel::oneshot;
+use serde_json::{Map, Number, Value};
 use tracing::warn;
 
 use super::bindings::{FFIRustV8Response, RustRefBrowser};
@@ -18,10 +19,11 @@ thread_local!(
         RefCell::default();
 );
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum RustV8Value {
     Unknown,
-    Array,
+    /// elements as `JSON.stringify` gave them
+    Array(Vec<Value>),
     ArrayBuffer,
     Bool(bool),
     Date,
@@ -29,12 +31,52 @@ pub enum RustV8Value {
     Function,
     Int(i32),
     Null,
-    Object,
+    /// properties as `JSON.stringify` gave them
+    Object(Map<String, Value>),
     String(String),
     UInt(u32),
     Undefined,
 }
 
+impl RustV8Value {
+    /// from the JSON sent for arrays and objects
+    pub fn from_json(json: &str) -> Self {
+        match serde_json::from_str(json) {
+            Ok(Value::Array(array)) => Self::Array(array),
+            Ok(Value::Object(object)) => Self::Object(object),
+            Ok(other) => {
+                warn!("expected array or object, got {:?}", other);
+                Self::Unknown
+            }
+            Err(e) => {
+                warn!("couldn't parse javascript result: {}", e);
+                Self::Unknown
+            }
+        }
+    }
+}
+
+impl From<RustV8Value> for Value {
+    /// values JSON can't hold become null, like `JSON.stringify` does in arrays
+    fn from(v8_value: RustV8Value) -> Self {
+        match v8_value {
+            RustV8Value::Array(array) => Self::Array(array),
+            RustV8Value::Bool(b) => Self::Bool(b),
+            RustV8Value::Double(d) => Number::from_f64(d).map_or(Self::Null, Self::Number),
+            RustV8Value::Int(i) => Self::Number(i.into()),
+            RustV8Value::Object(object) => Self::Object(object),
+            RustV8Value::String(s) => Self::String(s),
+            RustV8Value::UInt(u) => Self::Number(u.into()),
+            RustV8Value::Unknown
+            | RustV8Value::ArrayBuffer
+            | RustV8Value::Date
+            | RustV8Value::Function
+            | RustV8Value::Null
+            | RustV8Value::Undefined => Self::Null,
+        }
+    }
+}
+
 #[tracing::instrument(fields(_browser, response))]
 pub extern "C" fn on_javascript_callback(
     _browser: RustRefBrowser,
@@ -70,3 +112,47 @@ pub fn create_task() -> (oneshot::Receiver<FFIRustV8Response>, u64) {
 
     (receiver, task_id)
 }
+
+#[test]
+fn test_from_json() {
+    use serde_json::json;
+
+    assert_eq!(
+        RustV8Value::from_json(r#"[1, "two", null]"#),
+        RustV8Value::Array(vec![json!(1), json!("two"), Value::Null])
+    );
+
+    let RustV8Value::Object(object) = RustV8Value::from_json(r#"{"a": {"b": [true]}}"#) else {
+        panic!("not an object");
+    };
+    assert_eq!(object["a"], json!({ "b": [true] }));
+
+    assert_eq!(RustV8Value::from_json("1"), RustV8Value::Unknown);
+    assert_eq!(RustV8Value::from_json("{"), RustV8Value::Unknown);
+}
+
+#[test]
+fn test_deserialize() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Track {
+        title: String,
+        duration: f32,
+    }
+
+    let v8_value = RustV8Value::from_json(r#"{"title": "song", "duration": 12.5}"#);
+    let track: Track = serde_json::from_value(v8_value.into()).unwrap();
+    assert_eq!(
+        track,
+        Track {
+            title: "song".to_string(),
+            duration: 12.5
+        }
+    );
+
+    let n: u32 = serde_json::from_value(RustV8Value::UInt(3).into()).unwrap();
+    assert_eq!(n, 3);
+    assert_eq!(Value::from(RustV8Value::Double(f64::NAN)), Value::Null);
+    assert_eq!(Value::from(RustV8Value::Undefined), Value::Null);
+}
@@ -1,15 +1,20 @@
 mod bindings;
+pub mod blocklist;
 mod browser;
+mod dialog;
+mod download;
 mod javascript;
 mod mute_lose_focus;
 
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
-    mem,
+    fmt, mem,
     os::raw::c_int,
+    str::FromStr,
 };
 
+use clap::ValueEnum;
 use classicube_helpers::{async_manager, shared::FutureShared, WithInner};
 use futures::stream::{FuturesUnordered, StreamExt};
 use tokio::sync::broadcast;
@@ -18,7 +23,10 @@ use tracing::{debug, debug_span, error, warn, Instrument};
 pub use self::{
     bindings::{
         cef_interface_execute_process, Callbacks, RustRefApp, RustRefBrowser, RustRefClient,
+        TWITCH_PAGE_URL,
     },
+    dialog::{respond as respond_to_dialog, JsDialogKind},
+    download::{DownloadEvent, DownloadState},
     javascript::RustV8Value,
 };
 use self::{
@@ -27,7 +35,7 @@ use self::{
 };
 use crate::{
     entity_manager::{cef_paint_callback, TEXTURE_HEIGHT, TEXTURE_WIDTH},
-    error::{bail, Result, ResultExt},
+    error::{bail, Error, Result, ResultExt},
 };
 
 pub const CEF_DEFAULT_WIDTH: u16 = 1920;
@@ -37,11 +45,77 @@ pub const CEF_DEFAULT_HEIGHT: u16 = 1080;
 pub enum CefEvent {
     ContextInitialized(RustRefClient),
     BrowserCreated(RustRefBrowser),
+    BrowserLoadStart(RustRefBrowser),
+    /// from 0.0 to 1.0
+    BrowserLoadProgress(RustRefBrowser, f64),
+    BrowserLoadError(RustRefBrowser, LoadError),
     BrowserPageLoaded(RustRefBrowser),
     BrowserTitleChange(RustRefBrowser, String),
+    /// server's url policy stopped the page from going somewhere
+    BrowserNavigationBlocked(RustRefBrowser, String),
+    /// page wants a popup opened on a new screen
+    BrowserPopup(RustRefBrowser, String),
+    /// alert, confirm, prompt or beforeunload, waiting on `cef dialog`
+    BrowserJsDialog(RustRefBrowser, JsDialogKind, String),
+    /// `window.cef.postMessage(value)` from one of our player pages
+    PageMessage(RustRefBrowser, serde_json::Value),
+    /// started, progressed, finished or was refused, see `cef config downloads`
+    BrowserDownload(RustRefBrowser, DownloadEvent),
     BrowserClosed(RustRefBrowser),
 }
 
+/// what to do when a page opens a popup, like links with `target="_blank"`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PopupPolicy {
+    /// ignore it
+    Block,
+
+    /// load it in the screen that opened it
+    #[default]
+    Same,
+
+    /// open it on a new screen next to the one that opened it
+    Screen,
+}
+
+impl fmt::Display for PopupPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().ok_or(fmt::Error)?;
+        write!(f, "{}", value.get_name())
+    }
+}
+
+impl FromStr for PopupPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(<Self as ValueEnum>::from_str(s, true)?)
+    }
+}
+
+/// main frame failed to load
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    /// `cef_errorcode_t`, a negative net error
+    pub code: c_int,
+
+    /// like "ERR_NAME_NOT_RESOLVED"
+    pub text: String,
+
+    pub url: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.text.trim_start_matches("net::");
+        if text.is_empty() {
+            write!(f, "error {}", self.code)
+        } else {
+            write!(f, "{text}")
+        }
+    }
+}
+
 thread_local!(
     static CEF: FutureShared<Option<Cef>> = FutureShared::new(None);
 );
@@ -78,16 +152,31 @@ impl Cef {
     pub async fn initialize() -> Result<()> {
         debug!("initialize cef");
 
+        if let Err(e) = blocklist::reload().await {
+            warn!("blocklist: {}", e);
+        }
+
         let app = RustRefApp::create(Callbacks {
             on_context_initialized: Some(on_context_initialized_callback),
             on_after_created: Some(browser::on_after_created),
             on_before_close: Some(browser::on_before_close),
+            on_load_start: Some(browser::on_load_start),
+            on_loading_progress: Some(browser::on_loading_progress),
+            on_load_error: Some(browser::on_load_error),
             on_load_end: Some(browser::on_page_loaded),
             on_title_change: Some(browser::on_title_change),
             on_paint: Some(cef_paint_callback),
             get_view_rect: Some(browser::get_view_rect),
             on_javascript: Some(javascript::on_javascript_callback),
             on_certificate_error: Some(browser::on_certificate_error_callback),
+            on_before_popup: Some(browser::on_before_popup_callback),
+            on_js_dialog: Some(dialog::on_js_dialog_callback),
+            on_page_message: Some(browser::on_page_message),
+            on_before_download: Some(download::on_before_download_callback),
+            on_download_updated: Some(download::on_download_updated_callback),
+            on_before_resource_load: Some(blocklist::on_before_resource_load_callback),
+            has_blocklist: Some(blocklist::has_blocklist_callback),
+            on_before_browse: Some(browser::on_before_browse_callback),
         });
 
         let mut event_receiver = Self::create_event_listener();
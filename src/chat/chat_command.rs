@@ -11,7 +11,8 @@ extern "C" fn c_chat_command_callback(args: *const cc_string, args_count: c_int)
     let args = unsafe { slice::from_raw_parts(args, args_count.unsigned_abs() as _) };
     let args: Vec<String> = args.iter().map(ToString::to_string).collect();
 
-    let player_snapshot = PlayerSnapshot::from_entity_id(ENTITIES_SELF_ID as _).unwrap();
+    let mut player_snapshot = PlayerSnapshot::from_entity_id(ENTITIES_SELF_ID as _).unwrap();
+    player_snapshot.is_client_command = true;
 
     async_manager::spawn_local_on_main_thread(async move {
         if let Err(e) = commands::run(player_snapshot, args, true, true).await {
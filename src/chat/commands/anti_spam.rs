@@ -0,0 +1,221 @@
+//! Limits on `cef` commands other players type in chat, so one player can't
+//! flood everyone's client with screens.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use classicube_helpers::{
+    color::{SILVER, TEAL},
+    WithInner,
+};
+use classicube_sys::ENTITIES_SELF_ID;
+
+use super::{global, CefArgsSub};
+use crate::{
+    chat::{Chat, TAB_LIST},
+    entity_manager::{EntityManager, TargetEntity},
+    error::{bail, Result},
+    options::{self, REMOTE_SCREENS_MAX},
+};
+
+/// commands each player can run per `RATE_WINDOW`
+const RATE_LIMIT: usize = 5;
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// between close-alls from anyone else
+const CLOSE_ALL_COOLDOWN: Duration = Duration::from_secs(30);
+
+thread_local!(
+    // lowercase real name, recent commands
+    static SENDERS: RefCell<HashMap<String, Sender>> = RefCell::default();
+);
+
+thread_local!(
+    // lowercase real names
+    static IGNORED: RefCell<HashSet<String>> = RefCell::default();
+);
+
+thread_local!(
+    static LAST_CLOSE_ALL: Cell<Option<Instant>> = Cell::default();
+);
+
+#[derive(Default)]
+struct Sender {
+    times: VecDeque<Instant>,
+    /// printed that we're dropping their commands
+    warned: bool,
+}
+
+/// Errors if a command from another player shouldn't run.
+pub fn check(real_name: &str, sub: &CefArgsSub) -> Result<()> {
+    let key = real_name.to_lowercase();
+
+    if is_ignored(&key) {
+        bail!("ignored");
+    }
+
+    check_rate(real_name, key, Instant::now())?;
+
+    if let CefArgsSub::Global(commands) = sub {
+        match commands {
+            global::Commands::Create { name, .. } => check_screens(real_name, name.as_ref())?,
+
+            global::Commands::CloseAll => {
+                let now = Instant::now();
+                if LAST_CLOSE_ALL
+                    .get()
+                    .is_some_and(|last| now - last < CLOSE_ALL_COOLDOWN)
+                {
+                    bail!("close-all is cooling down");
+                }
+                LAST_CLOSE_ALL.set(Some(now));
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn check_rate(real_name: &str, key: String, now: Instant) -> Result<()> {
+    SENDERS.with(|cell| {
+        let senders = &mut *cell.borrow_mut();
+        let sender = senders.entry(key).or_default();
+
+        while sender
+            .times
+            .front()
+            .is_some_and(|&time| now - time >= RATE_WINDOW)
+        {
+            sender.times.pop_front();
+        }
+
+        if sender.times.len() >= RATE_LIMIT {
+            if !sender.warned {
+                sender.warned = true;
+                Chat::print(format!(
+                    "{TEAL}{real_name} {SILVER}is sending too many cef commands, skipping them \
+                     for a bit. Use {TEAL}cef ignore {real_name}{SILVER} to stop them for good"
+                ));
+            }
+            bail!("too many commands");
+        }
+
+        sender.warned = false;
+        sender.times.push_back(now);
+        Ok(())
+    })
+}
+
+fn check_screens(real_name: &str, name: Option<&String>) -> Result<()> {
+    // replacing one of their own
+    if let Some(id) = name.and_then(|name| name.get_entity_id().ok()) {
+        let is_theirs = EntityManager::with_entity(id, |entity| {
+            Ok(entity
+                .owner
+                .as_ref()
+                .is_some_and(|owner| owner.eq_ignore_ascii_case(real_name)))
+        })?;
+        if is_theirs {
+            return Ok(());
+        }
+    }
+
+    // everyone else's screens together, so joining with alts doesn't get around it
+    let self_name = get_self_real_name();
+    let count = EntityManager::with_all_entities(|entities| {
+        entities
+            .values()
+            .filter(|entity| {
+                entity.owner.as_ref().is_some_and(|owner| {
+                    self_name
+                        .as_ref()
+                        .map_or(true, |self_name| !owner.eq_ignore_ascii_case(self_name))
+                })
+            })
+            .count()
+    });
+
+    let max = REMOTE_SCREENS_MAX.get()?;
+    if count >= max as usize {
+        bail!("other players already have {} screens", count);
+    }
+
+    Ok(())
+}
+
+fn get_self_real_name() -> Option<String> {
+    TAB_LIST
+        .with_inner(|tab_list| {
+            let entry = tab_list.get(ENTITIES_SELF_ID as u8)?.upgrade()?;
+            Some(entry.get_real_name())
+        })
+        .flatten()
+}
+
+fn is_ignored(key: &str) -> bool {
+    IGNORED.with(|cell| cell.borrow().contains(key))
+}
+
+/// Returns false if they were already ignored.
+pub fn ignore(real_name: &str) -> bool {
+    IGNORED.with(|cell| cell.borrow_mut().insert(real_name.to_lowercase()))
+}
+
+/// Returns false if they weren't ignored.
+pub fn unignore(real_name: &str) -> bool {
+    IGNORED.with(|cell| cell.borrow_mut().remove(&real_name.to_lowercase()))
+}
+
+/// Reads the ignored list saved in options.
+pub fn load_ignored() {
+    let names = options::get_ignored();
+    IGNORED.with(|cell| *cell.borrow_mut() = names.into_iter().collect());
+}
+
+/// Writes the ignored list to options so it survives restarts.
+pub fn save_ignored() {
+    options::set_ignored(&get_ignored());
+}
+
+pub fn get_ignored() -> Vec<String> {
+    IGNORED.with(|cell| {
+        let mut names: Vec<String> = cell.borrow().iter().cloned().collect();
+        names.sort();
+        names
+    })
+}
+
+#[test]
+fn test_check_rate() {
+    let start = Instant::now();
+    for i in 0..RATE_LIMIT {
+        let now = start + Duration::from_millis(i as u64);
+        assert!(check_rate("Spammer", "spammer".to_string(), now).is_ok());
+    }
+    assert!(check_rate(
+        "Spammer",
+        "spammer".to_string(),
+        start + Duration::from_secs(1)
+    )
+    .is_err());
+    assert!(check_rate(
+        "Friend",
+        "friend".to_string(),
+        start + Duration::from_secs(1)
+    )
+    .is_ok());
+
+    // the first ones expired
+    assert!(check_rate("Spammer", "spammer".to_string(), start + RATE_WINDOW).is_ok());
+
+    assert!(ignore("Spammer"));
+    assert!(!ignore("spammer"));
+    assert!(is_ignored("spammer"));
+    assert_eq!(get_ignored(), vec!["spammer".to_string()]);
+    assert!(unignore("SPAMMER"));
+    assert!(!is_ignored("spammer"));
+}
@@ -150,6 +150,9 @@ pub async fn run(player_snapshot: PlayerSnapshot, commands: Commands) -> Result<
             let should_loop = r#loop;
 
             if let Some(id) = name.as_ref().and_then(|name| name.get_entity_id().ok()) {
+                EntityManager::with_entity(id, |entity| {
+                    entity.ensure_can_control(&player_snapshot)
+                })?;
                 drop(EntityManager::remove_entity(id).await);
             }
 
@@ -185,6 +188,10 @@ pub async fn run(player_snapshot: PlayerSnapshot, commands: Commands) -> Result<
                 entity_builder = entity_builder.name(name);
             }
 
+            if let Some(real_name) = player_snapshot.real_name.clone() {
+                entity_builder = entity_builder.owner(real_name);
+            }
+
             let entity_id = entity_builder.create().await?;
 
             if !global {
@@ -202,15 +209,32 @@ pub async fn run(player_snapshot: PlayerSnapshot, commands: Commands) -> Result<
                     )?;
 
                 // wait for browser to load
-                if page_load.await.is_err() {
-                    bail!("wait_for_page_load cancelled");
+                match page_load.await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => {
+                        bail!("failed to load: {}", error);
+                    }
+                    Err(_) => {
+                        bail!("wait_for_page_load cancelled");
+                    }
                 }
             }
         }
 
         Commands::CloseAll => {
-            async_manager::spawn_local_on_main_thread(async {
-                let _ignore_error = EntityManager::remove_all_entities().await;
+            // only the screens this player can control
+            let entity_ids: Vec<usize> = EntityManager::with_all_entities(|entities| {
+                entities
+                    .values()
+                    .filter(|entity| entity.can_control(&player_snapshot))
+                    .map(|entity| entity.id)
+                    .collect()
+            });
+
+            async_manager::spawn_local_on_main_thread(async move {
+                for entity_id in entity_ids {
+                    let _ignore_error = EntityManager::remove_entity(entity_id).await;
+                }
             });
         }
 
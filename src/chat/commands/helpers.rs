@@ -6,9 +6,24 @@ use ncollide3d::{
 };
 
 use crate::{
-    chat::PlayerSnapshot, entity_manager::CefEntity, error::Result, helpers::vec3_to_vector3,
+    chat::PlayerSnapshot,
+    entity_manager::{CefEntity, EntityManager, TargetEntity},
+    error::Result,
+    helpers::vec3_to_vector3,
 };
 
+/// The screen named, or the closest one, if the player may control it.
+pub fn get_controlled_entity_id(player: &PlayerSnapshot, name: Option<&String>) -> Result<usize> {
+    let entity_id = name.map_or_else(
+        || player.eye_position.get_entity_id(),
+        TargetEntity::get_entity_id,
+    )?;
+
+    EntityManager::with_entity(entity_id, |entity| entity.ensure_can_control(player))?;
+
+    Ok(entity_id)
+}
+
 pub fn move_entity(entity: &mut CefEntity, player: &PlayerSnapshot) {
     let dir = Vec3::get_dir_vector(player.Yaw.to_radians(), player.Pitch.to_radians());
 
@@ -3,17 +3,20 @@
 use std::time::Duration;
 
 use clap::Subcommand;
-use classicube_helpers::{async_manager, color::SILVER};
+use classicube_helpers::{
+    async_manager,
+    color::{SILVER, TEAL},
+};
 use classicube_sys::{
     Entities, Vec3, ENTITIES_SELF_ID, FACE_CONSTS, FACE_CONSTS_FACE_XMAX, FACE_CONSTS_FACE_XMIN,
     FACE_CONSTS_FACE_YMAX, FACE_CONSTS_FACE_YMIN, FACE_CONSTS_FACE_ZMAX, FACE_CONSTS_FACE_ZMIN,
 };
 
-use super::{helpers::get_camera_trace, Chat};
+use super::{anti_spam, helpers::get_camera_trace, Chat};
 use crate::{
     api,
     chat::{hidden_communication::whispers, PlayerSnapshot},
-    entity_manager::{EntityManager, TargetEntity},
+    entity_manager::{focus, layout, screenshot, EntityManager, TargetEntity},
     error::{Result, ResultExt},
     helpers::format_duration,
 };
@@ -41,15 +44,72 @@ pub enum Commands {
         name: Option<String>,
     },
 
+    /// Type into a screen with your keyboard until Escape is pressed
+    ///
+    /// Running it again stops typing
+    Focus {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
+    /// Save what a screen shows to the screenshots folder
+    Screenshot {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
     /// Re-sync all screens from someone else
     Sync { player_name: String },
 
+    /// Skip cef commands from a player until you restart
+    ///
+    /// Lists ignored players if no name is given
+    Ignore { player_name: Option<String> },
+
+    /// Stop ignoring a player's cef commands
+    Unignore { player_name: String },
+
+    /// Save or restore screen layouts for this server
+    #[command(
+        subcommand,
+        aliases(["layouts"]),
+        subcommand_required(true),
+        arg_required_else_help(true),
+    )]
+    Layout(LayoutCommands),
+
     /// Fake a crash via panic!()
     #[cfg(debug_assertions)]
     #[command(alias("panic"), hide(true))]
     Crash,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum LayoutCommands {
+    /// Save all synced screens
+    Save {
+        name: String,
+
+        /// Restore automatically when joining this map
+        #[arg(long, short)]
+        auto: bool,
+    },
+
+    /// Create all screens from a saved layout
+    #[command(alias("restore"))]
+    Load { name: String },
+
+    /// List saved layouts
+    #[command(alias("ls"))]
+    List,
+
+    /// Delete a saved layout
+    #[command(aliases(["remove", "rm"]))]
+    Delete { name: String },
+}
+
 pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
     match commands {
         Commands::Search { search } => {
@@ -120,6 +180,33 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             )?;
         }
 
+        Commands::Focus { name } => {
+            if focus::get_focused().is_some() {
+                focus::unfocus();
+            } else {
+                focus::focus(name.map_or_else(
+                    || player.eye_position.get_entity_id(),
+                    |name| name.get_entity_id(),
+                )?)?;
+            }
+        }
+
+        Commands::Screenshot { name } => {
+            let entity_id = EntityManager::with_entity(
+                name.map_or_else(
+                    || player.eye_position.get_entity_id(),
+                    |name| name.get_entity_id(),
+                )?,
+                |entity| Ok(entity.id),
+            )?;
+
+            let path = screenshot::take(entity_id).await?;
+            Chat::print(format!(
+                "{TEAL}Saved screenshot to {SILVER}{}",
+                path.display()
+            ));
+        }
+
         Commands::Sync { player_name } => {
             // TODO realname search
             let had_data = whispers::outgoing::query_whisper(&player_name).await?;
@@ -132,6 +219,64 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             // TODO 0 args, randomly chosen? maybe everyone like map join?
         }
 
+        Commands::Ignore { player_name } => {
+            if let Some(player_name) = player_name {
+                if anti_spam::ignore(&player_name) {
+                    anti_spam::save_ignored();
+                    Chat::print(format!("ignoring cef commands from {player_name}"));
+                } else {
+                    Chat::print(format!("already ignoring {player_name}"));
+                }
+            } else {
+                let names = anti_spam::get_ignored();
+                if names.is_empty() {
+                    Chat::print("not ignoring anyone");
+                } else {
+                    Chat::print(format!("ignoring {}", names.join(", ")));
+                }
+            }
+        }
+
+        Commands::Unignore { player_name } => {
+            if anti_spam::unignore(&player_name) {
+                anti_spam::save_ignored();
+                Chat::print(format!("stopped ignoring {player_name}"));
+            } else {
+                Chat::print(format!("{player_name} isn't ignored"));
+            }
+        }
+
+        Commands::Layout(commands) => match commands {
+            LayoutCommands::Save { name, auto } => {
+                let count = layout::save(&name, auto)?;
+                Chat::print(format!("saved {count} screens to layout {name:?}"));
+            }
+
+            LayoutCommands::Load { name } => {
+                let count = layout::load(&name).await?;
+                Chat::print(format!("loaded {count} screens from layout {name:?}"));
+            }
+
+            LayoutCommands::List => {
+                let layouts = layout::list()?;
+                if layouts.is_empty() {
+                    Chat::print("no saved layouts");
+                }
+                for (name, auto) in layouts {
+                    if auto {
+                        Chat::print(format!("{name} {SILVER}(auto)"));
+                    } else {
+                        Chat::print(name);
+                    }
+                }
+            }
+
+            LayoutCommands::Delete { name } => {
+                layout::delete(&name)?;
+                Chat::print(format!("deleted layout {name:?}"));
+            }
+        },
+
         #[cfg(debug_assertions)]
         Commands::Crash => {
             panic!("here's your crash!");
@@ -1,5 +1,6 @@
+mod anti_spam;
 mod global;
-mod helpers;
+pub mod helpers;
 mod local;
 mod options;
 mod screen;
@@ -44,6 +45,10 @@ pub enum CefArgsSub {
     Screen(screen::Commands),
 }
 
+pub fn initialize() {
+    anti_spam::load_ignored();
+}
+
 #[tracing::instrument(name = "commands::run", fields(player, is_self, show_errors, args = args.join(" ").as_str()))]
 pub async fn run(
     player: PlayerSnapshot,
@@ -58,6 +63,17 @@ pub async fn run(
     match CefArgs::try_parse_from(args) {
         Ok(args) => {
             debug!(?args, "CefArgs::try_parse_from");
+
+            // the server and /client have no real_name
+            if !is_self {
+                if let Some(real_name) = &player.real_name {
+                    if let Err(e) = anti_spam::check(real_name, &args.sub) {
+                        warn!("skipping {}'s command: {}", real_name, e);
+                        return Ok(());
+                    }
+                }
+            }
+
             let fut = async move {
                 match args.sub {
                     CefArgsSub::Global(args) => {
@@ -74,7 +90,7 @@ pub async fn run(
                         }
                     }
                     CefArgsSub::Screen(args) => {
-                        screen::run(player, args).await?;
+                        screen::run(player, args, is_self).await?;
                     }
                 }
 
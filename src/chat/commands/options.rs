@@ -1,9 +1,11 @@
 //! commands for setting local config options
 
 use clap::Subcommand;
+use classicube_helpers::color::RED;
 
 use super::Chat;
 use crate::{
+    cef::{blocklist, PopupPolicy},
     chat::hidden_communication::CURRENT_MAP_THEME,
     entity_manager::EntityManager,
     error::{Error, Result},
@@ -55,6 +57,52 @@ pub enum ConfigCommands {
         percent: Option<f32>,
     },
 
+    /// Keep synced screens in time with the player you synced from
+    Heartbeat {
+        #[arg(help(format!("[default: {}]", options::HEARTBEAT.default())))]
+        enabled: Option<bool>,
+    },
+
+    /// Forward hovering, clicking and scrolling to the screen you look at
+    ///
+    /// The game still gets the same clicks, so this also breaks and places blocks.
+    Mouse {
+        #[arg(help(format!("[default: {}]", options::MOUSE.default())))]
+        enabled: Option<bool>,
+    },
+
+    /// What to do when a page opens a popup
+    Popups {
+        #[arg(help(format!("[default: {}]", options::POPUPS.default())))]
+        policy: Option<PopupPolicy>,
+    },
+
+    /// Save files pages download to cef/downloads
+    Downloads {
+        #[arg(help(format!("[default: {}]", options::DOWNLOADS.default())))]
+        enabled: Option<bool>,
+    },
+
+    /// Cancel downloads bigger than this many megabytes
+    DownloadMaxMb {
+        #[arg(help(format!("[default: {}]", options::DOWNLOAD_MAX_MB.default())))]
+        megabytes: Option<u32>,
+    },
+
+    /// Cancel requests to ads and trackers listed in cef/blocklist.txt
+    ///
+    /// Takes hosts files or simple adblock filters. Also reloads the file.
+    Blocklist {
+        #[arg(help(format!("[default: {}]", options::BLOCKLIST.default())))]
+        enabled: Option<bool>,
+    },
+
+    /// How many screens each other player can have open on your client
+    RemoteScreensMax {
+        #[arg(help(format!("[default: {}]", options::REMOTE_SCREENS_MAX.default())))]
+        screens: Option<u32>,
+    },
+
     /// Changes default frame rate of newly created browsers
     FrameRate {
         #[arg(help(format!("[default: {}]", options::FRAME_RATE.default())))]
@@ -179,6 +227,107 @@ pub async fn run(commands: Commands) -> Result<()> {
             }
         }
 
+        ConfigCommands::Heartbeat { enabled } => {
+            let value = options::HEARTBEAT.get()?;
+            if let Some(enabled) = enabled {
+                options::HEARTBEAT.set(enabled);
+                Chat::print(format!(
+                    "heartbeat: {} -> {}",
+                    value,
+                    options::HEARTBEAT.get()?
+                ));
+            } else {
+                Chat::print(format!("heartbeat: {value}"));
+            }
+        }
+
+        ConfigCommands::Mouse { enabled } => {
+            let value = options::MOUSE.get()?;
+            if let Some(enabled) = enabled {
+                options::MOUSE.set(enabled);
+                Chat::print(format!("mouse: {} -> {}", value, options::MOUSE.get()?));
+                if enabled {
+                    Chat::print(format!(
+                        "{RED}Clicking a screen still breaks and places blocks behind it"
+                    ));
+                }
+            } else {
+                Chat::print(format!("mouse: {value}"));
+            }
+        }
+
+        ConfigCommands::Popups { policy } => {
+            let value = options::POPUPS.get()?;
+            if let Some(policy) = policy {
+                options::POPUPS.set(policy);
+                Chat::print(format!("popups: {} -> {}", value, options::POPUPS.get()?));
+            } else {
+                Chat::print(format!("popups: {value}"));
+            }
+        }
+
+        ConfigCommands::Downloads { enabled } => {
+            let value = options::DOWNLOADS.get()?;
+            if let Some(enabled) = enabled {
+                options::DOWNLOADS.set(enabled);
+                Chat::print(format!(
+                    "downloads: {} -> {}",
+                    value,
+                    options::DOWNLOADS.get()?
+                ));
+            } else {
+                Chat::print(format!("downloads: {value}"));
+            }
+        }
+
+        ConfigCommands::DownloadMaxMb { megabytes } => {
+            let value = options::DOWNLOAD_MAX_MB.get()?;
+            if let Some(megabytes) = megabytes {
+                options::DOWNLOAD_MAX_MB.set(megabytes);
+                Chat::print(format!(
+                    "download-max-mb: {} -> {}",
+                    value,
+                    options::DOWNLOAD_MAX_MB.get()?
+                ));
+            } else {
+                Chat::print(format!("download-max-mb: {value}"));
+            }
+        }
+
+        ConfigCommands::RemoteScreensMax { screens } => {
+            let value = options::REMOTE_SCREENS_MAX.get()?;
+            if let Some(screens) = screens {
+                options::REMOTE_SCREENS_MAX.set(screens);
+                Chat::print(format!(
+                    "remote-screens-max: {} -> {}",
+                    value,
+                    options::REMOTE_SCREENS_MAX.get()?
+                ));
+            } else {
+                Chat::print(format!("remote-screens-max: {value}"));
+            }
+        }
+
+        ConfigCommands::Blocklist { enabled } => {
+            let value = options::BLOCKLIST.get()?;
+            if let Some(enabled) = enabled {
+                options::BLOCKLIST.set(enabled);
+                Chat::print(format!(
+                    "blocklist: {} -> {}",
+                    value,
+                    options::BLOCKLIST.get()?
+                ));
+            } else {
+                Chat::print(format!("blocklist: {value}"));
+            }
+
+            if let Some(len) = blocklist::reload().await? {
+                Chat::print(format!("{len} rules from {}", blocklist::display_path()));
+            } else if options::BLOCKLIST.get()? {
+                Chat::print(format!("no {} found", blocklist::display_path()));
+            }
+        }
+
         ConfigCommands::FrameRate { fps } => {
             let value = options::FRAME_RATE.get()?;
             if let Some(fps) = fps {
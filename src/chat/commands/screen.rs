@@ -6,20 +6,20 @@ use std::{
 };
 
 use async_recursion::async_recursion;
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
 use classicube_helpers::{
     async_manager,
     color::{GOLD, SILVER, TEAL},
 };
 
-use super::helpers::{get_click_coords, move_entity};
+use super::helpers::{get_click_coords, get_controlled_entity_id, move_entity};
 use crate::{
-    cef::Cef,
+    cef::{self, Cef},
     chat::{Chat, PlayerSnapshot},
-    entity_manager::{CefEntity, EntityManager, TargetEntity},
+    entity_manager::{CefEntity, EntityManager, RepeatMode, ScreenAccess, TargetEntity},
     error::{bail, ensure, Error, Result, ResultExt},
-    helpers::format_duration,
-    player::{PlayerBuilder, PlayerTrait, VolumeMode},
+    helpers::{fnv1a, format_duration},
+    player::{Player, PlayerBuilder, PlayerTrait, VolumeMode},
 };
 
 #[derive(Debug, Subcommand)]
@@ -33,8 +33,16 @@ pub enum Commands {
     },
 
     /// Play or queue something
-    #[command(aliases(["play", "load"]))]
+    #[command(
+        aliases(["play", "load"]),
+        args_conflicts_with_subcommands(true),
+        subcommand_negates_reqs(true),
+        override_usage("cef queue [OPTIONS] <URL>...\n       cef queue <COMMAND>")
+    )]
     Queue {
+        #[command(subcommand)]
+        command: Option<QueueCommands>,
+
         /// Name of screen
         #[arg(long, short)]
         name: Option<String>,
@@ -116,6 +124,27 @@ pub enum Commands {
         name: Option<String>,
     },
 
+    /// Go back a page
+    Back {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
+    /// Go forward a page
+    Forward {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
+    /// Stop loading the page
+    StopLoading {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
     /// Change angles of screen
     #[command(alias("angle"))]
     Angles {
@@ -155,6 +184,64 @@ pub enum Commands {
         words: Vec<String>,
     },
 
+    /// Answer an alert, confirm or prompt from a screen
+    ///
+    /// Prompts use the text if given, otherwise their default text
+    Dialog {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        #[arg(value_enum)]
+        action: DialogAction,
+
+        #[arg(allow_hyphen_values(true))]
+        words: Vec<String>,
+    },
+
+    /// Zoom the page of a screen, in percent
+    ///
+    /// Shows the current zoom if no percent is given
+    Zoom {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        percent: Option<f32>,
+    },
+
+    /// Add a stylesheet to every page a screen loads
+    ///
+    /// Shows the current stylesheet if none is given
+    #[command(override_usage("cef css [OPTIONS] [CSS]...\n       cef css [OPTIONS] --clear"))]
+    Css {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        /// Remove the stylesheet
+        #[arg(long, conflicts_with("css"))]
+        clear: bool,
+
+        #[arg(allow_hyphen_values(true))]
+        css: Vec<String>,
+    },
+
+    /// Choose who can control a screen you created
+    ///
+    /// Shows who can if no mode is given
+    Access {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        #[arg(value_enum)]
+        mode: Option<AccessMode>,
+
+        /// Real names of the players, for "players"
+        players: Vec<String>,
+    },
+
     /// Set the resolution of a screen
     Resolution {
         /// Name of screen
@@ -261,6 +348,18 @@ pub enum Commands {
         speed: f32,
     },
 
+    /// Set what happens when the current item finishes
+    ///
+    /// Shows the current mode if no mode is given
+    Repeat {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        #[arg(value_enum)]
+        mode: Option<RepeatMode>,
+    },
+
     /// Fade volume of screen
     #[command(override_usage("cef fade [OPTIONS] [FROM] <TO> <SECONDS>"))]
     Fade {
@@ -279,15 +378,107 @@ pub enum Commands {
     },
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DialogAction {
+    /// OK, or leave the page
+    Accept,
+
+    /// Cancel, or stay on the page
+    Dismiss,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum AccessMode {
+    /// Anyone
+    Everyone,
+
+    /// Only you
+    Owner,
+
+    /// You and the players listed
+    Players,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QueueCommands {
+    /// Show the queue
+    #[command(alias("ls"))]
+    List {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
+    /// Remove an item from the queue
+    #[command(alias("rm"))]
+    Remove {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        index: usize,
+    },
+
+    /// Move an item to another position in the queue
+    #[command(alias("mv"))]
+    Move {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        from: usize,
+
+        to: usize,
+    },
+
+    /// Shuffle the queue
+    Shuffle {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        /// Shuffle seed, defaults to one made from the queue's urls
+        seed: Option<u64>,
+    },
+
+    /// Remove everything from the queue
+    Clear {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+    },
+
+    /// Queue something to play after the current item
+    Next {
+        /// Name of screen
+        #[arg(long, short)]
+        name: Option<String>,
+
+        /// Start paused
+        #[arg(long, short('a'))]
+        no_autoplay: bool,
+
+        /// Loop after track finishes playing
+        #[arg(long, short)]
+        r#loop: bool,
+
+        /// Don't show Now Playing messages
+        ///
+        /// (may not be allowed on some urls)
+        #[arg(long, short('q'), alias("quiet"))]
+        silent: bool,
+
+        #[arg(required(true), allow_hyphen_values(true))]
+        url: Vec<String>,
+    },
+}
+
 #[async_recursion(?Send)]
-pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
+pub async fn run(player: PlayerSnapshot, commands: Commands, is_self: bool) -> Result<()> {
     match commands {
         Commands::Here { name } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     move_entity(entity, &player);
 
@@ -297,6 +488,14 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         }
 
         Commands::Queue {
+            command: Some(command),
+            ..
+        } => {
+            run_queue(player, command).await?;
+        }
+
+        Commands::Queue {
+            command: None,
             name,
             skip,
             no_autoplay,
@@ -304,50 +503,31 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             silent,
             url,
         } => {
-            // hack so that newline continuation messages are concated
-            let url = url.join("");
-
             let autoplay = !no_autoplay;
             let should_loop = r#loop;
 
-            let mut players = PlayerBuilder::new()
-                .autoplay(autoplay)
-                .should_loop(should_loop)
-                .silent(silent)
-                .build(&url)
-                .await?;
-
-            for p in players.drain(..) {
-                let kind = p.type_name();
-                let url = p.get_url();
-                EntityManager::with_entity(
-                    name.as_ref().map_or_else(
-                        || player.eye_position.get_entity_id(),
-                        TargetEntity::get_entity_id,
-                    )?,
-                    |entity| {
-                        if let Some(queue_size) = entity.queue(p)? {
-                            Chat::print(format!(
-                                "{TEAL}Queued {GOLD}{queue_size} {TEAL}{kind} {SILVER}{url}"
-                            ));
-
-                            if skip {
-                                entity.skip()?;
-                            }
-                        }
+            queue_urls(
+                &player,
+                name,
+                &url,
+                autoplay,
+                should_loop,
+                silent,
+                |entity, p| {
+                    let queue_size = entity.queue(p)?;
+                    if queue_size.is_some() && skip {
+                        entity.skip()?;
+                    }
 
-                        Ok(())
-                    },
-                )?;
-            }
+                    Ok(queue_size)
+                },
+            )
+            .await?;
         }
 
         Commands::Skip { name } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 CefEntity::skip,
             )?;
         }
@@ -388,32 +568,20 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         // }
         Commands::Stop { name } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 CefEntity::stop,
             )?;
         }
 
         Commands::Close { name } => {
-            let entity_id = EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
-                |entity| Ok(entity.id),
-            )?;
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
 
             EntityManager::remove_entity(entity_id).await?;
         }
 
         Commands::Scale { name, scale } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 move |entity| {
                     entity.set_scale(scale);
 
@@ -428,10 +596,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             height,
         } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 move |entity| {
                     entity.set_size(width, height);
 
@@ -441,23 +606,34 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         }
 
         Commands::Reload { name } => {
-            let entity_id = EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
-                |entity| Ok(entity.id),
-            )?;
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
             let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
             browser.reload()?;
         }
 
+        Commands::Back { name } => {
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
+            let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
+            ensure!(browser.can_go_back(), "no page to go back to");
+            browser.go_back()?;
+        }
+
+        Commands::Forward { name } => {
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
+            let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
+            ensure!(browser.can_go_forward(), "no page to go forward to");
+            browser.go_forward()?;
+        }
+
+        Commands::StopLoading { name } => {
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
+            let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
+            browser.stop_load()?;
+        }
+
         Commands::Angles { name, yaw, pitch } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     entity.entity.RotY = yaw;
 
@@ -473,13 +649,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         Commands::Click { name, x, y } => {
             if let Some(x) = x {
                 if let Some(y) = y {
-                    let entity_id = EntityManager::with_entity(
-                        name.map_or_else(
-                            || player.eye_position.get_entity_id(),
-                            |name| name.get_entity_id(),
-                        )?,
-                        |entity| Ok(entity.id),
-                    )?;
+                    let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
 
                     let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
                     browser.send_click(x, y)?;
@@ -487,10 +657,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             } else {
                 let (entity_id, entity_pos, [entity_pitch, entity_yaw], entity_scale, entity_size) =
                     EntityManager::with_entity(
-                        name.map_or_else(
-                            || player.eye_position.get_entity_id(),
-                            |name| name.get_entity_id(),
-                        )?,
+                        get_controlled_entity_id(&player, name.as_ref())?,
                         |entity| {
                             Ok((
                                 entity.id,
@@ -525,30 +692,126 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         Commands::Type { name, words } => {
             let text = words.join(" ");
 
-            let entity_id = EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
-                |entity| Ok(entity.id),
-            )?;
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
 
             let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
             browser.send_text(text)?;
         }
 
-        Commands::Resolution {
+        Commands::Dialog {
             name,
-            width,
-            height,
+            action,
+            words,
         } => {
-            let entity_id = EntityManager::with_entity(
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
+            let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
+
+            let text = if words.is_empty() {
+                None
+            } else {
+                Some(words.join(" "))
+            };
+            cef::respond_to_dialog(
+                browser.get_identifier(),
+                matches!(action, DialogAction::Accept),
+                text,
+            )?;
+        }
+
+        Commands::Zoom { name, percent } => {
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    if let Some(percent) = percent {
+                        entity.set_zoom(percent / 100.0)?;
+                    }
+
+                    Chat::print(format!("{TEAL}Zoom {GOLD}{}%", entity.zoom * 100.0));
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        Commands::Css { name, clear, css } => {
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    if clear {
+                        entity.set_user_css(None)?;
+                    } else if !css.is_empty() {
+                        entity.set_user_css(Some(css.join(" ")))?;
+                    }
+
+                    if let Some(user_css) = &entity.user_css {
+                        Chat::print(format!("{TEAL}Stylesheet {SILVER}{user_css}"));
+                    } else {
+                        Chat::print(format!("{TEAL}No stylesheet"));
+                    }
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        Commands::Access {
+            name,
+            mode,
+            players,
+        } => {
+            EntityManager::with_entity(
                 name.map_or_else(
                     || player.eye_position.get_entity_id(),
                     |name| name.get_entity_id(),
                 )?,
-                |entity| Ok(entity.id),
+                |entity| {
+                    if let Some(mode) = mode {
+                        let owner = entity
+                            .owner
+                            .as_deref()
+                            .chain_err(|| "screen has no owner")?;
+                        // not everyone who can control it
+                        ensure!(
+                            player.is_client_command
+                                || ScreenAccess::Owner
+                                    .allows(Some(owner), player.real_name.as_deref()),
+                            "only {} can change who controls this screen",
+                            owner
+                        );
+
+                        entity.access = match mode {
+                            AccessMode::Everyone => ScreenAccess::Everyone,
+                            AccessMode::Owner => ScreenAccess::Owner,
+                            AccessMode::Players => {
+                                ensure!(!players.is_empty(), "no players given");
+                                ScreenAccess::Players(players.clone())
+                            }
+                        };
+                    }
+
+                    let owner = entity.owner.as_deref().unwrap_or("nobody");
+                    let access = match &entity.access {
+                        ScreenAccess::Everyone => "everyone".to_string(),
+                        ScreenAccess::Owner => "only the owner".to_string(),
+                        ScreenAccess::Players(names) => {
+                            format!("the owner and {}", names.join(", "))
+                        }
+                    };
+                    Chat::print(format!(
+                        "{TEAL}Owned by {SILVER}{owner}{TEAL}, controlled by {GOLD}{access}"
+                    ));
+
+                    Ok(())
+                },
             )?;
+        }
+
+        Commands::Resolution {
+            name,
+            width,
+            height,
+        } => {
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
 
             let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
             Cef::resize_browser(&browser, width, height)?;
@@ -562,10 +825,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             multiplier,
         } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     if global {
                         entity
@@ -645,10 +905,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             ensure!(seconds.is_sign_positive(), "not positive");
 
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     let browser = entity.browser.as_ref().chain_err(|| "no browser")?;
 
@@ -690,14 +947,12 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
                     args.push(name.to_string());
                 }
 
-                super::run(player.clone(), args, true, true).await?;
+                // still counts against their screens if someone else said it
+                super::run(player.clone(), args, is_self, true).await?;
             }
 
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     entity.entity.Position.set(x, y, z);
 
@@ -734,7 +989,15 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
                         Chat::print(format!("At time {time}"));
                     }
 
-                    Chat::print(url);
+                    Chat::print(&url);
+
+                    // web pages can be clicked around, show where it ended up
+                    if let (Player::Web(_), Some(browser)) = (&entity.player, &entity.browser) {
+                        let current_url = browser.get_url();
+                        if !current_url.is_empty() && current_url != url {
+                            Chat::print(format!("{TEAL}Now at {SILVER}{current_url}"));
+                        }
+                    }
 
                     if !entity.queue.is_empty() {
                         let len = entity.queue.len();
@@ -761,10 +1024,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
 
         Commands::Resume { name } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     let browser = entity.browser.as_ref().chain_err(|| "no browser")?;
 
@@ -775,10 +1035,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         }
         Commands::Pause { name } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     let browser = entity.browser.as_ref().chain_err(|| "no browser")?;
 
@@ -789,10 +1046,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
         }
         Commands::Speed { name, speed } => {
             EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
+                get_controlled_entity_id(&player, name.as_ref())?,
                 |entity| {
                     entity.player.set_speed(entity.browser.as_ref(), speed)?;
                     Ok(())
@@ -800,6 +1054,22 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
             )?;
         }
 
+        Commands::Repeat { name, mode } => {
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    if let Some(mode) = mode {
+                        entity.repeat = mode;
+                    }
+
+                    let mode = format!("{:?}", entity.repeat).to_lowercase();
+                    Chat::print(format!("{TEAL}Repeat {GOLD}{mode}"));
+
+                    Ok(())
+                },
+            )?;
+        }
+
         Commands::Fade {
             name,
             from_or_to,
@@ -826,13 +1096,7 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
                 (None, to, seconds)
             };
 
-            let entity_id = EntityManager::with_entity(
-                name.map_or_else(
-                    || player.eye_position.get_entity_id(),
-                    |name| name.get_entity_id(),
-                )?,
-                move |entity| Ok(entity.id),
-            )?;
+            let entity_id = get_controlled_entity_id(&player, name.as_ref())?;
 
             let from = if let Some(from) = maybe_from {
                 from
@@ -902,3 +1166,212 @@ pub async fn run(player: PlayerSnapshot, commands: Commands) -> Result<()> {
 
     Ok(())
 }
+
+async fn queue_urls<F>(
+    player: &PlayerSnapshot,
+    name: Option<String>,
+    url: &[String],
+    autoplay: bool,
+    should_loop: bool,
+    silent: bool,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(&mut CefEntity, Player) -> Result<Option<usize>>,
+{
+    // hack so that newline continuation messages are concated
+    let url = url.join("");
+
+    let mut players = PlayerBuilder::new()
+        .autoplay(autoplay)
+        .should_loop(should_loop)
+        .silent(silent)
+        .build(&url)
+        .await?;
+
+    for p in players.drain(..) {
+        let kind = p.type_name();
+        let url = p.get_url();
+        EntityManager::with_entity(get_controlled_entity_id(player, name.as_ref())?, |entity| {
+            if let Some(position) = f(entity, p)? {
+                Chat::print(format!(
+                    "{TEAL}Queued {GOLD}{position} {TEAL}{kind} {SILVER}{url}"
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// queue commands show positions starting at 1
+fn queue_index(index: usize) -> Result<usize> {
+    index
+        .checked_sub(1)
+        .chain_err(|| "queue positions start at 1")
+}
+
+async fn run_queue(player: PlayerSnapshot, commands: QueueCommands) -> Result<()> {
+    match commands {
+        QueueCommands::List { name } => {
+            EntityManager::with_entity(
+                name.map_or_else(
+                    || player.eye_position.get_entity_id(),
+                    |name| name.get_entity_id(),
+                )?,
+                |entity| {
+                    if entity.queue.is_empty() {
+                        Chat::print(format!("{TEAL}Queue is empty"));
+                        return Ok(());
+                    }
+
+                    let len = entity.queue.len();
+                    Chat::print(format!("{GOLD}{len} {TEAL}items in queue:"));
+
+                    for (i, (player, title)) in entity.queue.iter().enumerate() {
+                        let index = i + 1;
+                        let type_name = player.type_name();
+                        let title = title.lock().unwrap();
+                        let title = title.clone().unwrap_or_else(|| player.get_url());
+                        Chat::print(format!("{GOLD}{index} {TEAL}{type_name} {SILVER}{title}"));
+                    }
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        QueueCommands::Remove { name, index } => {
+            let index = queue_index(index)?;
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    let removed = entity.queue_remove(index)?;
+                    let url = removed.get_url();
+                    Chat::print(format!("{TEAL}Removed {GOLD}{} {SILVER}{url}", index + 1));
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        QueueCommands::Move { name, from, to } => {
+            let from = queue_index(from)?;
+            let to = queue_index(to)?;
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| entity.queue_move(from, to),
+            )?;
+        }
+
+        QueueCommands::Shuffle { name, seed } => {
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    // everyone runs this command, so the default seed has to be
+                    // something we all agree on
+                    let seed = seed.unwrap_or_else(|| {
+                        let urls = entity
+                            .queue
+                            .iter()
+                            .map(|(player, _)| player.get_url())
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        fnv1a(urls.as_bytes())
+                    });
+                    entity.queue_shuffle(seed);
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        QueueCommands::Clear { name } => {
+            EntityManager::with_entity(
+                get_controlled_entity_id(&player, name.as_ref())?,
+                |entity| {
+                    let count = entity.queue_clear();
+                    Chat::print(format!(
+                        "{TEAL}Removed {GOLD}{count} {TEAL}items from queue"
+                    ));
+
+                    Ok(())
+                },
+            )?;
+        }
+
+        QueueCommands::Next {
+            name,
+            no_autoplay,
+            r#loop,
+            silent,
+            url,
+        } => {
+            let autoplay = !no_autoplay;
+            let should_loop = r#loop;
+
+            // keep playlists in order
+            let mut index = 0;
+            queue_urls(
+                &player,
+                name,
+                &url,
+                autoplay,
+                should_loop,
+                silent,
+                |entity, p| {
+                    let position = entity.queue_at(index, p)?;
+                    if position.is_some() {
+                        index += 1;
+                    }
+
+                    Ok(position)
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_queue_subcommands() {
+    use clap::Parser;
+
+    use super::{CefArgs, CefArgsSub};
+
+    let parse = |cmd: &str| {
+        let args = CefArgs::try_parse_from(format!("cef {cmd}").split(' ')).unwrap();
+        let CefArgsSub::Screen(Commands::Queue { command, url, .. }) = args.sub else {
+            panic!("not a queue command: {cmd}");
+        };
+        (command, url)
+    };
+
+    let (command, url) = parse("play -n a https://b.com/");
+    assert!(command.is_none());
+    assert_eq!(url, ["https://b.com/"]);
+
+    let (command, _) = parse("queue list -n a");
+    assert!(matches!(
+        command,
+        Some(QueueCommands::List { name: Some(_) })
+    ));
+
+    let (command, _) = parse("queue move 3 1");
+    assert!(matches!(
+        command,
+        Some(QueueCommands::Move { from: 3, to: 1, .. })
+    ));
+
+    let (command, _) = parse("queue next -l https://b.com/");
+    assert!(matches!(
+        command,
+        Some(QueueCommands::Next { r#loop: true, .. })
+    ));
+
+    assert!(CefArgs::try_parse_from(["cef", "queue"]).is_err());
+}
@@ -16,6 +16,22 @@ pub fn is_cef_request_whisper(message: &str) -> bool {
     (is_outgoing_whisper(message) || is_incoming_whisper(message)) && message.contains("?CEF?")
 }
 
+pub fn is_cef_heartbeat_request_whisper(message: &str) -> bool {
+    (is_outgoing_whisper(message) || is_incoming_whisper(message)) && message.contains("?CEFT?")
+}
+
+pub fn is_cef_heartbeat_reply_whisper(message: &str) -> bool {
+    (is_outgoing_whisper(message) || is_incoming_whisper(message)) && message.contains("!CEFT!")
+}
+
+/// schema version the asker can read, old versions didn't send one
+pub fn get_cef_request_schema_version(message: &str) -> u8 {
+    message
+        .split_once("?CEF?")
+        .and_then(|(_, version)| version.trim().parse().ok())
+        .unwrap_or(1)
+}
+
 pub fn is_cef_reply_whisper(message: &str) -> bool {
     (is_outgoing_whisper(message) || is_incoming_whisper(message)) && message.contains("!CEF!")
 }
@@ -32,6 +48,16 @@ pub fn is_map_theme_message(message: &str) -> Option<&str> {
     }
 }
 
+pub fn is_url_policy_message(message: &str) -> Option<&str> {
+    let message = remove_color_left(message);
+
+    if message.to_ascii_lowercase().starts_with("url policy:") {
+        Some(remove_color_left(message.get("url policy:".len()..)?.trim()).trim())
+    } else {
+        None
+    }
+}
+
 pub fn is_global_cef_message(message: &str) -> Option<&str> {
     let message = remove_color_left(message);
 
@@ -42,6 +68,23 @@ pub fn is_global_cef_message(message: &str) -> Option<&str> {
     }
 }
 
+#[test]
+fn test_is_cef_heartbeat_whisper() {
+    assert!(is_cef_heartbeat_request_whisper("&9[>] name: &f?CEFT?"));
+    assert!(!is_cef_request_whisper("&9[>] name: &f?CEFT?"));
+    assert!(!is_cef_heartbeat_request_whisper("&9[>] name: &f?CEF?2"));
+    assert!(is_cef_heartbeat_reply_whisper("&9[>] name: &f!CEFT!abc"));
+    assert!(!is_cef_reply_whisper("&9[>] name: &f!CEFT!abc"));
+}
+
+#[test]
+fn test_get_cef_request_schema_version() {
+    assert_eq!(get_cef_request_schema_version("&9[>] name: &f?CEF?"), 1);
+    assert_eq!(get_cef_request_schema_version("&9[>] name: &f?CEF?2"), 2);
+    assert_eq!(get_cef_request_schema_version("&9[>] name: &f?CEF?12 "), 12);
+    assert_eq!(get_cef_request_schema_version("&9[>] name: &f?CEF?x"), 1);
+}
+
 #[test]
 fn test_is_global_cef_message() {
     assert_eq!(is_global_cef_message("&fcef is good"), Some("is good"));
@@ -56,6 +99,23 @@ fn test_is_global_cef_message() {
     assert_eq!(is_global_cef_message("&fceff is BAD"), None);
 }
 
+#[test]
+fn test_is_url_policy_message() {
+    assert_eq!(
+        is_url_policy_message("&eURL Policy: &fallow youtube.com"),
+        Some("allow youtube.com")
+    );
+    assert_eq!(
+        is_url_policy_message("url policy:deny a.com"),
+        Some("deny a.com")
+    );
+    assert_eq!(
+        is_url_policy_message("&fname: url policy: allow a.com"),
+        None
+    );
+    assert_eq!(is_url_policy_message("url"), None);
+}
+
 pub fn is_continuation_message(mut message: &str) -> Option<&str> {
     if message.starts_with("> ") {
         message = message.get(2..)?;
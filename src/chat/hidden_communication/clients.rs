@@ -5,7 +5,7 @@ use classicube_sys::ENTITIES_SELF_ID;
 use futures::{future::RemoteHandle, prelude::*};
 use tracing::{debug, warn};
 
-use super::{wait_for_message, SHOULD_BLOCK};
+use super::{encoding, wait_for_message, SHOULD_BLOCK};
 use crate::{
     chat::{
         helpers::{is_clients_message, is_clients_start_message},
@@ -13,7 +13,6 @@ use crate::{
         is_continuation_message, Chat, TAB_LIST,
     },
     error::{Result, ResultExt},
-    plugin::APP_NAME,
 };
 
 thread_local!(
@@ -135,17 +134,19 @@ async fn get_clients() -> Result<Vec<String>> {
     Ok(messages)
 }
 
+/// (min, max) schema versions from "cefsync2-3", see `encoding::schema_range_tag`
+fn parse_schema_range(word: &str) -> Option<(u8, u8)> {
+    let (min, max) = word.strip_prefix("cefsync")?.split_once('-')?;
+
+    Some((min.parse().ok()?, max.parse().ok()?))
+}
+
 fn get_names_with_cef(messages: &[String]) -> Result<HashSet<String>> {
-    // cef0.13.2-alpha.0
-    let app_name_without_last_number = format!(
-        "{}.",
-        APP_NAME
-            .splitn(3, '.')
-            .take(2)
-            .collect::<Vec<_>>()
-            .join(".")
-    );
-    debug!("{:#?} {:?}", messages, app_name_without_last_number);
+    // ClassiCube 1.3.6 cef1.13.3 cefsync2-3
+    //
+    // anyone who reads a schema version we can send, and sends one we can read;
+    // cef versions from before the schema was versioned don't advertise a range
+    debug!("{:#?}", messages);
 
     let mut names_with_cef: HashSet<String> = HashSet::new();
     for message in messages {
@@ -157,7 +158,14 @@ fn get_names_with_cef(messages: &[String]) -> Result<HashSet<String>> {
         if let Some(right) = right.get(2..) {
             let names: HashSet<String> = right.split(", ").map(ToString::to_string).collect();
 
-            if left.contains(&app_name_without_last_number) {
+            let compatible =
+                left.split_whitespace()
+                    .filter_map(parse_schema_range)
+                    .any(|(min, max)| {
+                        min <= encoding::SCHEMA_VERSION && encoding::MIN_SCHEMA_VERSION <= max
+                    });
+
+            if compatible {
                 for name in names {
                     names_with_cef.insert(name);
                 }
@@ -198,52 +206,50 @@ async fn process_clients_response(messages: Vec<String>) -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_parse_schema_range() {
+    assert_eq!(parse_schema_range("cefsync2-3"), Some((2, 3)));
+    assert_eq!(parse_schema_range("cefsync10-12"), Some((10, 12)));
+    assert_eq!(parse_schema_range("cefsync2"), None);
+    assert_eq!(parse_schema_range("cef1.13.3"), None);
+    assert_eq!(parse_schema_range("+cs3.5.15"), None);
+}
+
 #[test]
 fn test_get_names_with_cef() {
-    let parts = APP_NAME
-        .get(3..)
-        .unwrap()
-        .splitn(3, '.')
-        .collect::<Vec<_>>();
-    let without_last_number = format!(
-        "cef{}",
-        parts.iter().copied().take(2).collect::<Vec<_>>().join(".")
-    );
-
-    {
-        let lines = vec![
-            format!("ClassiCube 1.2.4 + {APP_NAME}: name1"),
-            format!("ClassiCube 1.2.4 {APP_NAME} +cs3.5.15 + Ponies v2.1: name2"),
-            format!("ClassiCube 1.2.4 + {APP_NAME} +cs3.5.15 + Ponies v2.1: name3"),
-            format!("ClassiCube 1.2.4 {APP_NAME} cs3.5.15 + Ponies v2.1: name4"),
-            format!("ClassiCube 1.2.4 {}.0: name5", without_last_number),
-            format!("ClassiCube 1.2.4 {}.: name6", without_last_number),
-        ];
-
-        let r = get_names_with_cef(&lines).unwrap();
-        assert!(r.contains("name1"));
-        assert!(r.contains("name2"));
-        assert!(r.contains("name3"));
-        assert!(r.contains("name4"));
-        assert!(r.contains("name5"));
-        assert!(r.contains("name6"));
-    }
+    let app_name = crate::plugin::APP_NAME;
+    let ours = encoding::schema_range_tag();
+    let min = encoding::MIN_SCHEMA_VERSION;
+    let max = encoding::SCHEMA_VERSION;
+
+    let lines = vec![
+        format!("ClassiCube 1.2.4 {app_name} {ours}: name1"),
+        format!("ClassiCube 1.2.4 {app_name} {ours} +cs3.5.15 + Ponies v2.1: name2"),
+        format!(
+            "ClassiCube 1.2.4 + cef99.0.0 cefsync{min}-{}: name3",
+            max + 5
+        ),
+        format!(
+            "ClassiCube 1.2.4 + cef0.1.0 cefsync{}-{max}: name4",
+            min - 1
+        ),
+        format!("ClassiCube 1.2.4 {app_name}: name5"),
+        format!("ClassiCube 1.2.4 + cef0.0.0: name6"),
+        format!("ClassiCube 1.2.4 cefsync0-{}: name7", min - 1),
+        format!("ClassiCube 1.2.4 cefsync{}-{}: name8", max + 1, max + 5),
+        format!("ClassiCube 1.2.4 + cefsyncx: name9"),
+    ];
 
-    {
-        let with_next_minor = format!("cef{}.99.0", parts[0]);
-        let lines = vec![
-            format!("ClassiCube 1.2.4 {}: name7", with_next_minor),
-            format!("ClassiCube 1.2.4 + {}: name8", with_next_minor),
-            format!("ClassiCube 1.2.4 cef0.0.0: name9"),
-            format!("ClassiCube 1.2.4 + cef0.0.0: name10"),
-        ];
-
-        let r = get_names_with_cef(&lines).unwrap();
-        assert!(!r.contains("name7"));
-        assert!(!r.contains("name8"));
-        assert!(!r.contains("name9"));
-        assert!(!r.contains("name10"));
-    }
+    let r = get_names_with_cef(&lines).unwrap();
+    assert!(r.contains("name1"));
+    assert!(r.contains("name2"));
+    assert!(r.contains("name3"));
+    assert!(r.contains("name4"));
+    assert!(!r.contains("name5"));
+    assert!(!r.contains("name6"));
+    assert!(!r.contains("name7"));
+    assert!(!r.contains("name8"));
+    assert!(!r.contains("name9"));
 }
 
 #[test]
@@ -1,19 +1,47 @@
-use std::{collections::VecDeque, io::Cursor, time::Duration};
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use base64::{prelude::BASE64_STANDARD, Engine};
-use serde::{Deserialize, Serialize};
-use tracing::debug;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use tracing::{debug, warn};
 
 use crate::{
     cef::Cef,
-    entity_manager::{EntityBuilder, EntityManager},
-    error::Result,
-    player::{Player, PlayerTrait},
+    entity_manager::{
+        CefEntity, EntityBuilder, EntityManager, RepeatMode, ScreenAccess, USER_CSS_MAX_LENGTH,
+    },
+    error::{ensure, Result, ResultExt},
+    helpers::or_default,
+    player::{PlaybackState, Player, PlayerTrait},
 };
 
+/// Schema version of `Message` that we send.
+///
+/// Adding fields (with `#[serde(default)]`), `Player` variants or variants of enums
+/// read with `or_default` doesn't need a bump, older versions ignore unknown fields,
+/// skip unknown players and use the default for unknown variants.
+pub const SCHEMA_VERSION: u8 = 2;
+
+/// Oldest schema version we can read, and the oldest reader we will send to.
+///
+/// Bump this together with `SCHEMA_VERSION` when making a breaking change.
+pub const MIN_SCHEMA_VERSION: u8 = 2;
+
+/// Added to our app name so /clients shows which schema versions we read,
+/// like `cefsync2-3`.
+pub fn schema_range_tag() -> String {
+    format!("cefsync{MIN_SCHEMA_VERSION}-{SCHEMA_VERSION}")
+}
+
+const MAGIC: &[u8] = b"CEF";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LightEntity {
     player: Player,
+    #[serde(deserialize_with = "skip_unknown")]
     queue: VecDeque<Player>,
 
     name: Option<String>,
@@ -23,74 +51,238 @@ pub struct LightEntity {
     rotation: (f32, f32),
     position: (f32, f32, f32),
     background_color: u32,
+    #[serde(default, deserialize_with = "or_default")]
+    repeat: RepeatMode,
+    #[serde(default, deserialize_with = "or_default")]
+    state: PlaybackState,
+    #[serde(default = "default_zoom")]
+    zoom: f32,
+    #[serde(default)]
+    user_css: Option<String>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default, deserialize_with = "access_or_owner")]
+    access: ScreenAccess,
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+impl LightEntity {
+    pub fn from_entity(entity: &CefEntity) -> Self {
+        let e = &entity.entity;
+
+        let player = entity.player.clone();
+        let queue = entity
+            .queue
+            .iter()
+            .map(|(player, _)| player)
+            .cloned()
+            .collect();
+
+        let name = entity.name.clone();
+        let resolution = entity.browser.as_ref().map(Cef::get_browser_size);
+        let size = entity.get_size();
+        let scale = entity.get_scale();
+        let rotation = (e.RotX, e.RotY);
+        let position = (e.Position.X, e.Position.Y, e.Position.Z);
+        let background_color = entity.background_color;
+        let repeat = entity.repeat;
+        let state = entity.player.get_playback_state();
+        let zoom = entity.zoom;
+        let user_css = entity.user_css.clone();
+        let owner = entity.owner.clone();
+        let access = entity.access.clone();
+
+        Self {
+            player,
+            queue,
+            name,
+            resolution,
+            size,
+            scale,
+            rotation,
+            position,
+            background_color,
+            repeat,
+            state,
+            zoom,
+            user_css,
+            owner,
+            access,
+        }
+    }
+
+    pub fn into_builder(self) -> EntityBuilder {
+        let mut player = self.player;
+        if self.state != PlaybackState::Playing {
+            // stay paused where they are
+            let _ignore = player.set_autoplay(None, false);
+        }
+
+        let mut builder = EntityBuilder::new(player)
+            .queue(self.queue)
+            .size(self.size.0, self.size.1)
+            .scale(self.scale)
+            .rotation(self.rotation.0, self.rotation.1)
+            .position(self.position.0, self.position.1, self.position.2)
+            .background_color(self.background_color)
+            .repeat(self.repeat)
+            .zoom(self.zoom)
+            .access(self.access);
+
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+
+        if let Some(user_css) = self.user_css {
+            if user_css.len() <= USER_CSS_MAX_LENGTH {
+                builder = builder.user_css(user_css);
+            } else {
+                warn!("skipping stylesheet of {} bytes", user_css.len());
+            }
+        }
+
+        if let Some(owner) = self.owner {
+            builder = builder.owner(owner);
+        }
+
+        if let Some(res) = self.resolution {
+            builder = builder.resolution(res.0, res.1);
+        }
+
+        builder
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Message {
+    /// milliseconds since unix epoch when the sender captured `entities`
+    pub captured_at: u64,
+    #[serde(deserialize_with = "skip_unknown")]
     pub entities: Vec<LightEntity>,
 }
 
+impl Message {
+    /// How long ago the sender captured this message.
+    ///
+    /// Clamped to `max` (how long we've been waiting for it)
+    /// because our clocks might not agree.
+    pub fn elapsed(&self, max: Duration) -> Duration {
+        elapsed_since(self.captured_at, max)
+    }
+}
+
+/// milliseconds since unix epoch
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+/// time since `captured_at` (from `now_millis` on someone else's computer),
+/// clamped to `max` because our clocks might not agree
+pub fn elapsed_since(captured_at: u64, max: Duration) -> Duration {
+    let captured_at = UNIX_EPOCH + Duration::from_millis(captured_at);
+
+    SystemTime::now()
+        .duration_since(captured_at)
+        .unwrap_or_default()
+        .min(max)
+}
+
+/// skip items we don't understand, like players added in newer versions
+pub fn skip_unknown<'de, D, C, T>(deserializer: D) -> std::result::Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    C: FromIterator<T>,
+    T: DeserializeOwned,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+
+    Ok(values
+        .into_iter()
+        .filter_map(|value| match serde_json::from_value(value) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("skipping unknown item: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// access we don't understand only lets the owner in
+fn access_or_owner<'de, D>(deserializer: D) -> std::result::Result<ScreenAccess, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+
+    Ok(serde_json::from_value(value).unwrap_or_else(|e| {
+        warn!("unknown screen access, only allowing the owner: {}", e);
+        ScreenAccess::Owner
+    }))
+}
+
 /// to base64
-pub fn encode(message: &Message) -> Result<String> {
-    let data = bincode::serialize(message)?;
+pub fn encode<T: Serialize>(message: &T) -> Result<String> {
+    let mut data = MAGIC.to_vec();
+    data.push(SCHEMA_VERSION);
+    serde_json::to_writer(&mut data, message)?;
     let compressed_data = zstd::encode_all(Cursor::new(&data), 0)?;
 
     Ok(BASE64_STANDARD.encode(compressed_data))
 }
 
 /// from base64
-pub fn decode<T: AsRef<[u8]>>(input: T) -> Result<Message> {
+pub fn decode<T: DeserializeOwned, I: AsRef<[u8]>>(input: I) -> Result<T> {
     let compressed_data = BASE64_STANDARD.decode(input)?;
     let data = zstd::decode_all(Cursor::new(&compressed_data))?;
 
-    Ok(bincode::deserialize(&data)?)
+    let data = data
+        .strip_prefix(MAGIC)
+        .chain_err(|| "message from an old version without a schema version")?;
+    let (&version, data) = data.split_first().chain_err(|| "no schema version")?;
+    ensure!(
+        version >= MIN_SCHEMA_VERSION,
+        "message schema version {} is older than {}",
+        version,
+        MIN_SCHEMA_VERSION
+    );
+    if version > SCHEMA_VERSION {
+        debug!("decoding newer schema version {}", version);
+    }
+
+    Ok(serde_json::from_slice(data)?)
 }
 
 pub fn create_message() -> Message {
     let light_entities: Vec<_> = EntityManager::with_all_entities(|entities| {
         entities
-            .iter()
-            .filter(|(_id, entity)| entity.should_send)
-            .map(|(&_id, entity)| {
-                let e = &entity.entity;
-
-                let player = entity.player.clone();
-                let queue = entity
-                    .queue
-                    .iter()
-                    .map(|(player, _)| player)
-                    .cloned()
-                    .collect();
-
-                let name = entity.name.clone();
-                let resolution = entity.browser.as_ref().map(Cef::get_browser_size);
-                let size = entity.get_size();
-                let scale = entity.get_scale();
-                let rotation = (e.RotX, e.RotY);
-                let position = (e.Position.X, e.Position.Y, e.Position.Z);
-                let background_color = entity.background_color;
-
-                LightEntity {
-                    player,
-                    queue,
-                    name,
-                    resolution,
-                    size,
-                    scale,
-                    rotation,
-                    position,
-                    background_color,
-                }
-            })
+            .values()
+            .filter(|entity| entity.should_send)
+            .map(LightEntity::from_entity)
             .collect()
     });
 
     Message {
+        captured_at: now_millis(),
         entities: light_entities,
     }
 }
 
-pub async fn received_message(mut message: Message) -> Result<bool> {
+/// `max_elapsed` is how long ago we asked `responder` for this message
+pub async fn received_message(
+    mut message: Message,
+    responder: &str,
+    max_elapsed: Duration,
+) -> Result<bool> {
     let mut had_data = false;
 
     // only remove synced browsers
@@ -106,41 +298,187 @@ pub async fn received_message(mut message: Message) -> Result<bool> {
         EntityManager::remove_entity(id).await?;
     }
 
+    // everything kept playing while the message was whispered to us,
+    // our own page load time is measured by the players once they start
+    let elapsed = message.elapsed(max_elapsed);
+    debug!("message captured {:?} ago", elapsed);
+
     for mut info in message.entities.drain(..) {
         debug!("creating {:#?}", info);
 
-        if let Ok(time) = info.player.get_current_time() {
-            if time > Duration::from_secs(1) {
-                // this is a couple seconds behind because of the load time
-                // of the browser page and whisper delay, so add a couple seconds
-                if let Player::YouTube(ref mut yt) = &mut info.player {
-                    yt.time = time + Duration::from_secs(4);
-                } else if let Player::Media(ref mut media) = &mut info.player {
-                    media.time = time + Duration::from_secs(4);
-                }
-            }
+        // anyone can answer, only owners get to say who owns their screens
+        if info
+            .owner
+            .as_ref()
+            .is_some_and(|owner| !owner.eq_ignore_ascii_case(responder))
+        {
+            info.owner = None;
+            info.access = ScreenAccess::default();
         }
 
-        let mut builder = EntityBuilder::new(info.player)
-            .queue(info.queue)
-            .size(info.size.0, info.size.1)
-            .scale(info.scale)
-            .rotation(info.rotation.0, info.rotation.1)
-            .position(info.position.0, info.position.1, info.position.2)
-            .background_color(info.background_color);
-
-        if let Some(name) = info.name {
-            builder = builder.name(name);
-        }
+        if info.player.get_autoplay() && info.state == PlaybackState::Playing {
+            let elapsed = elapsed.mul_f32(info.player.get_speed());
+            match &mut info.player {
+                Player::YouTube(yt) => {
+                    yt.time += elapsed;
+                    yt.synced = true;
+                }
+                Player::Media(media) => {
+                    media.time += elapsed;
+                    media.synced = true;
+                }
+                Player::Twitch(twitch) if twitch.is_video() => {
+                    twitch.time += elapsed;
+                    twitch.synced = true;
+                }
+                Player::Embed(embed) => {
+                    embed.time += elapsed;
+                    embed.synced = true;
+                }
+                Player::SoundCloud(soundcloud) => {
+                    soundcloud.time += elapsed;
+                    soundcloud.synced = true;
+                }
 
-        if let Some(res) = info.resolution {
-            builder = builder.resolution(res.0, res.1);
+                _ => {}
+            }
         }
 
-        builder.create().await?;
+        info.into_builder().create().await?;
 
         had_data = true;
     }
 
     Ok(had_data)
 }
+
+#[test]
+fn test_message_elapsed() {
+    let now = now_millis();
+    let message = |captured_at| Message {
+        captured_at,
+        entities: Vec::new(),
+    };
+    let max = Duration::from_secs(5);
+
+    let elapsed = message(now - 2000).elapsed(max);
+    assert!(
+        elapsed >= Duration::from_secs(2) && elapsed < max,
+        "{elapsed:?}"
+    );
+
+    // their clock is ahead of ours
+    assert_eq!(message(now + 60_000).elapsed(max), Duration::ZERO);
+
+    // their clock is behind ours
+    assert_eq!(message(now - 60_000).elapsed(max), max);
+}
+
+#[test]
+fn test_decode_tolerant() {
+    use crate::player::{VolumeMode, WebPlayer};
+
+    let web = || Player::Web(WebPlayer::from_input("https://www.classicube.net/").unwrap());
+    let message = Message {
+        captured_at: 1,
+        entities: vec![LightEntity {
+            player: web(),
+            queue: vec![web()].into(),
+            name: Some("a".to_string()),
+            resolution: None,
+            size: (16, 9),
+            scale: 0.25,
+            rotation: (0.0, 0.0),
+            position: (1.0, 2.0, 3.0),
+            background_color: 0xFFFF_FFFF,
+            repeat: RepeatMode::All,
+            state: PlaybackState::Paused,
+            zoom: 1.5,
+            user_css: Some("body { color: red; }".to_string()),
+            owner: Some("DJ".to_string()),
+            access: ScreenAccess::Players(vec!["Friend".to_string()]),
+        }],
+    };
+
+    let decoded: Message = decode(encode(&message).unwrap()).unwrap();
+    assert_eq!(decoded.entities.len(), 1);
+    assert_eq!(decoded.entities[0].name(), Some("a"));
+    assert_eq!(decoded.entities[0].repeat, RepeatMode::All);
+    assert_eq!(decoded.entities[0].state, PlaybackState::Paused);
+    assert!((decoded.entities[0].zoom - 1.5).abs() < f32::EPSILON);
+    assert_eq!(
+        decoded.entities[0].user_css.as_deref(),
+        Some("body { color: red; }")
+    );
+    assert_eq!(decoded.entities[0].owner.as_deref(), Some("DJ"));
+    assert_eq!(
+        decoded.entities[0].access,
+        ScreenAccess::Players(vec!["Friend".to_string()])
+    );
+
+    // pretend a newer version added a field and a player type
+    let mut value = serde_json::to_value(&message).unwrap();
+    value["new_field"] = true.into();
+    let entities = value["entities"].as_array_mut().unwrap();
+    entities[0]["queue"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({ "NewPlayer": {} }));
+    let mut new_entity = entities[0].clone();
+    new_entity["player"] = serde_json::json!({ "NewPlayer": {} });
+    entities.push(new_entity);
+
+    let mut data = MAGIC.to_vec();
+    data.push(SCHEMA_VERSION + 1);
+    serde_json::to_writer(&mut data, &value).unwrap();
+    let encoded = BASE64_STANDARD.encode(zstd::encode_all(Cursor::new(&data), 0).unwrap());
+
+    let decoded: Message = decode(encoded).unwrap();
+    assert_eq!(decoded.entities.len(), 1);
+    assert_eq!(decoded.entities[0].queue.len(), 1);
+
+    // senders from before zoom, user_css, owner and access were synced
+    let mut value = serde_json::to_value(&message).unwrap();
+    let entity = value["entities"][0].as_object_mut().unwrap();
+    entity.remove("zoom");
+    entity.remove("user_css");
+    entity.remove("owner");
+    entity.remove("access");
+
+    let mut data = MAGIC.to_vec();
+    data.push(SCHEMA_VERSION);
+    serde_json::to_writer(&mut data, &value).unwrap();
+    let encoded = BASE64_STANDARD.encode(zstd::encode_all(Cursor::new(&data), 0).unwrap());
+
+    let decoded: Message = decode(encoded).unwrap();
+    assert!((decoded.entities[0].zoom - 1.0).abs() < f32::EPSILON);
+    assert_eq!(decoded.entities[0].user_css, None);
+    assert_eq!(decoded.entities[0].owner, None);
+    assert_eq!(decoded.entities[0].access, ScreenAccess::Everyone);
+
+    // enum variants from a newer version
+    let mut value = serde_json::to_value(&message).unwrap();
+    let entity = &mut value["entities"][0];
+    entity["repeat"] = "Shuffle".into();
+    entity["state"] = serde_json::json!({ "Buffering": 0.5 });
+    entity["access"] = serde_json::json!({ "Team": "red" });
+
+    let mut data = MAGIC.to_vec();
+    data.push(SCHEMA_VERSION);
+    serde_json::to_writer(&mut data, &value).unwrap();
+    let encoded = BASE64_STANDARD.encode(zstd::encode_all(Cursor::new(&data), 0).unwrap());
+
+    let decoded: Message = decode(encoded).unwrap();
+    assert_eq!(decoded.entities.len(), 1);
+    assert_eq!(decoded.entities[0].repeat, RepeatMode::Off);
+    assert_eq!(decoded.entities[0].state, PlaybackState::Playing);
+    assert_eq!(decoded.entities[0].access, ScreenAccess::Owner);
+    assert_eq!(
+        or_default::<_, VolumeMode>(serde_json::json!("Stereo")).unwrap(),
+        VolumeMode::default()
+    );
+
+    // old versions without the envelope
+    let encoded = BASE64_STANDARD.encode(zstd::encode_all(Cursor::new(&[0u8; 8]), 0).unwrap());
+    assert!(decode::<Message, _>(encoded).is_err());
+}
@@ -12,14 +12,17 @@ use tracing::{debug, info, warn};
 use super::wait_for_message;
 use crate::{
     chat::{
-        helpers::{is_global_cef_message, is_map_theme_message},
+        helpers::{is_global_cef_message, is_map_theme_message, is_url_policy_message},
         hidden_communication::SHOULD_BLOCK,
         is_continuation_message, Chat, PlayerSnapshot,
     },
     entity_manager::{EntityBuilder, EntityManager},
     error::{bail, Result},
     options,
-    player::{PlayerBuilder, PlayerTrait, VolumeMode},
+    player::{
+        url_policy::{self, UrlPolicy},
+        PlayerBuilder, PlayerTrait, VolumeMode,
+    },
 };
 
 thread_local!(
@@ -148,26 +151,7 @@ pub async fn listen_loop() {
         } else if let Some(first_part_input) = is_map_theme_message(&message) {
             debug!("got map_theme url first part {:?}", message);
 
-            let mut input_parts: Vec<String> = vec![first_part_input.to_string()];
-
-            let timeout_result = async_manager::timeout(Duration::from_secs(1), async {
-                loop {
-                    let message = wait_for_message().await;
-                    if let Some(continuation) = is_continuation_message(&message) {
-                        input_parts.push(continuation.to_string());
-                    } else {
-                        debug!("stopping because of other message {:?}", message);
-                        break;
-                    }
-                }
-            })
-            .await;
-
-            if timeout_result.is_none() {
-                debug!("stopping because of timeout");
-            }
-
-            let full_input: String = input_parts.join("");
+            let full_input = read_continuations(first_part_input).await;
             info!("map_theme {:?}", full_input);
 
             async_manager::spawn_local_on_main_thread(async move {
@@ -179,10 +163,47 @@ pub async fn listen_loop() {
                     }
                 }
             });
+        } else if let Some(first_part_input) = is_url_policy_message(&message) {
+            debug!("got url policy first part {:?}", message);
+
+            let full_input = read_continuations(first_part_input).await;
+            info!("url policy {:?}", full_input);
+
+            match UrlPolicy::parse(&full_input) {
+                Ok(policy) => url_policy::set(policy),
+
+                Err(e) => {
+                    warn!("url policy listen_loop: {}", e);
+                }
+            }
         }
     }
 }
 
+/// joins the "> " lines that follow a long message
+async fn read_continuations(first_part: &str) -> String {
+    let mut parts: Vec<String> = vec![first_part.to_string()];
+
+    let timeout_result = async_manager::timeout(Duration::from_secs(1), async {
+        loop {
+            let message = wait_for_message().await;
+            if let Some(continuation) = is_continuation_message(&message) {
+                parts.push(continuation.to_string());
+            } else {
+                debug!("stopping because of other message {:?}", message);
+                break;
+            }
+        }
+    })
+    .await;
+
+    if timeout_result.is_none() {
+        debug!("stopping because of timeout");
+    }
+
+    parts.join("")
+}
+
 async fn handle_map_theme_url(input: String) -> Result<()> {
     debug!("map_theme got {:?}", input);
 
@@ -0,0 +1,219 @@
+//! keeps synced screens in time with the player we synced from
+//!
+//! We whisper them a heartbeat request once, and from then on they whisper
+//! everyone who asked the current time of each of their screens every so
+//! often. Our update loop nudges our screens towards it.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use classicube_helpers::{async_manager, WithInner};
+use futures::{future::RemoteHandle, prelude::*};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{
+    encoding::{elapsed_since, now_millis, skip_unknown},
+    whispers::{incoming, outgoing},
+};
+use crate::{
+    chat::ENTITIES,
+    entity_manager::{EntityManager, SyncTarget},
+    helpers::or_default,
+    options,
+    player::{PlaybackState, PlayerTrait},
+};
+
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// how old a heartbeat we didn't ask for can be, whispers arrive in a second or two
+pub const MAX_ELAPSED: Duration = Duration::from_secs(5);
+
+thread_local!(
+    static SYNC_SOURCE: RefCell<Option<String>> = RefCell::default();
+);
+
+thread_local!(
+    /// asked our sync source to send us heartbeats
+    static SUBSCRIBED: Cell<bool> = Cell::default();
+);
+
+thread_local!(
+    /// real name, entity id of players who asked us for heartbeats
+    static FOLLOWERS: RefCell<HashMap<String, u8>> = RefCell::default();
+);
+
+thread_local!(
+    static LOOP_HANDLE: Cell<Option<RemoteHandle<()>>> = Cell::default();
+);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeartbeatScreen {
+    name: Option<String>,
+    position: (f32, f32, f32),
+    time: Duration,
+    #[serde(default, deserialize_with = "or_default")]
+    state: PlaybackState,
+    /// None from versions that didn't send it
+    #[serde(default)]
+    speed: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// milliseconds since unix epoch when the sender captured `screens`
+    captured_at: u64,
+    #[serde(deserialize_with = "skip_unknown")]
+    pub screens: Vec<HeartbeatScreen>,
+}
+
+/// real name of the player we last synced from
+pub fn set_sync_source(real_name: Option<String>) {
+    SYNC_SOURCE.with(|cell| {
+        *cell.borrow_mut() = real_name;
+    });
+    SUBSCRIBED.set(false);
+}
+
+/// whether to apply heartbeats `real_name` sends without us asking
+pub fn is_subscribed_to(real_name: &str) -> bool {
+    options::HEARTBEAT.get().unwrap_or(false)
+        && SUBSCRIBED.get()
+        && SYNC_SOURCE.with(|cell| cell.borrow().as_deref() == Some(real_name))
+}
+
+/// send heartbeats to them until one of us leaves the map
+pub fn add_follower(real_name: String, id: u8) {
+    FOLLOWERS.with(|cell| {
+        cell.borrow_mut().insert(real_name, id);
+    });
+}
+
+pub fn on_new_map() {
+    set_sync_source(None);
+    FOLLOWERS.with(|cell| cell.borrow_mut().clear());
+}
+
+pub fn create_message() -> Heartbeat {
+    let screens = EntityManager::with_all_entities(|entities| {
+        entities
+            .values()
+            .filter(|entity| entity.should_send)
+            .filter_map(|entity| {
+                let time = entity.player.get_current_time().ok()?;
+                let e = &entity.entity;
+
+                Some(HeartbeatScreen {
+                    name: entity.name.clone(),
+                    position: (e.Position.X, e.Position.Y, e.Position.Z),
+                    time,
+                    state: entity.player.get_playback_state(),
+                    speed: Some(entity.player.get_speed()),
+                })
+            })
+            .collect()
+    });
+
+    Heartbeat {
+        captured_at: now_millis(),
+        screens,
+    }
+}
+
+/// `max_elapsed` is how long ago we asked for this message
+pub fn received_message(heartbeat: Heartbeat, max_elapsed: Duration) {
+    let elapsed = elapsed_since(heartbeat.captured_at, max_elapsed);
+    let now = Instant::now();
+
+    EntityManager::with_all_entities(|entities| {
+        for screen in heartbeat.screens {
+            // entity ids are different for everyone, but synced screens
+            // have the same name and position
+            let maybe_entity = entities.values_mut().find(|entity| {
+                let e = &entity.entity;
+
+                entity.should_send
+                    && entity.name == screen.name
+                    && (e.Position.X - screen.position.0).abs() < 0.01
+                    && (e.Position.Y - screen.position.1).abs() < 0.01
+                    && (e.Position.Z - screen.position.2).abs() < 0.01
+            });
+
+            if let Some(entity) = maybe_entity {
+                // ours could be nudged by an earlier heartbeat
+                let speed = screen
+                    .speed
+                    .filter(|speed| (0.25..=4.0).contains(speed))
+                    .unwrap_or_else(|| entity.player.get_speed());
+                // a paused screen stays where it is
+                let time = if screen.state == PlaybackState::Playing {
+                    screen.time + elapsed.mul_f32(speed)
+                } else {
+                    screen.time
+                };
+
+                entity.sync_target = Some(SyncTarget::new(time, now, speed, screen.state));
+            }
+        }
+    });
+}
+
+async fn heartbeat_loop() {
+    loop {
+        async_manager::sleep(INTERVAL).await;
+
+        broadcast().await;
+
+        if !options::HEARTBEAT.get().unwrap_or(false) || SUBSCRIBED.get() {
+            continue;
+        }
+
+        let Some(real_name) = SYNC_SOURCE.with(|cell| cell.borrow().clone()) else {
+            continue;
+        };
+
+        match outgoing::query_heartbeat(&real_name).await {
+            Ok(()) => SUBSCRIBED.set(true),
+            Err(e) => warn!("heartbeat from {} failed: {}", real_name, e),
+        }
+    }
+}
+
+async fn broadcast() {
+    // forget players who left the map
+    let followers: Vec<String> = FOLLOWERS.with(|cell| {
+        let followers = &mut *cell.borrow_mut();
+        followers.retain(|_real_name, id| {
+            ENTITIES
+                .with_inner(|entities| entities.get(*id).is_some())
+                .unwrap_or(false)
+        });
+        followers.keys().cloned().collect()
+    });
+
+    for real_name in followers {
+        if let Err(e) = incoming::send_heartbeat(&real_name).await {
+            warn!("heartbeat to {} failed: {}", real_name, e);
+        }
+    }
+}
+
+pub fn start() {
+    debug!("heartbeat start");
+
+    let (f, remote_handle) = heartbeat_loop().remote_handle();
+    async_manager::spawn_local_on_main_thread(f);
+
+    LOOP_HANDLE.with(move |cell| {
+        cell.set(Some(remote_handle));
+    });
+}
+
+pub fn stop() {
+    LOOP_HANDLE.with(move |cell| {
+        cell.set(None);
+    });
+}
@@ -1,6 +1,7 @@
 pub mod clients;
 pub mod encoding;
 pub mod global_control;
+pub mod heartbeat;
 pub mod whispers;
 
 use std::{
@@ -18,6 +19,7 @@ use tracing::debug;
 
 pub use self::global_control::CURRENT_MAP_THEME;
 use super::SIMULATING;
+use crate::player::url_policy;
 
 thread_local!(
     static SHOULD_BLOCK: Cell<bool> = const { Cell::new(false) };
@@ -74,6 +76,7 @@ pub fn initialize() {
 
     whispers::start_listening();
     global_control::start_listening();
+    heartbeat::start();
 
     install_message_handler();
 }
@@ -81,6 +84,9 @@ pub fn initialize() {
 pub fn reset() {
     debug!("reset hidden_communication");
 
+    // new server
+    url_policy::set(None);
+
     if unsafe { Server.IsSinglePlayer } != 0 {
         return;
     }
@@ -92,6 +98,7 @@ pub fn on_new_map() {
     if unsafe { Server.IsSinglePlayer } == 0 {
         clients::stop_query();
         global_control::on_new_map();
+        heartbeat::on_new_map();
     }
 }
 
@@ -105,6 +112,7 @@ pub fn on_new_map_loaded() {
 pub fn shutdown() {
     debug!("shutdown hidden_communication");
 
+    heartbeat::stop();
     global_control::stop_listening();
     whispers::stop_listening();
 }
@@ -3,15 +3,17 @@ use std::time::Duration;
 use classicube_helpers::{async_manager, shared::FutureShared, WithInner};
 use tracing::{debug, info, warn};
 
-use super::{encoding, wait_for_message, SHOULD_BLOCK};
+use super::{encoding, heartbeat, read_encoded, wait_for_message, SHOULD_BLOCK};
 use crate::{
     chat::{
         helpers::{
-            is_cef_reply_whisper, is_cef_request_whisper, is_incoming_whisper, is_outgoing_whisper,
+            get_cef_request_schema_version, is_cef_heartbeat_reply_whisper,
+            is_cef_heartbeat_request_whisper, is_cef_request_whisper, is_incoming_whisper,
+            is_outgoing_whisper,
         },
         is_continuation_message, Chat, ENTITIES, TAB_LIST,
     },
-    error::{Result, ResultExt},
+    error::{ensure, Result, ResultExt},
 };
 
 pub async fn listen_loop() {
@@ -19,7 +21,9 @@ pub async fn listen_loop() {
         let message = wait_for_message().await;
 
         // incoming info request whisper
-        if is_incoming_whisper(&message) && is_cef_request_whisper(&message) {
+        if is_incoming_whisper(&message)
+            && (is_cef_request_whisper(&message) || is_cef_heartbeat_request_whisper(&message))
+        {
             SHOULD_BLOCK.set(true);
 
             info!("incoming_whisper {:?}", message);
@@ -33,6 +37,25 @@ pub async fn listen_loop() {
                     }
                 }
             });
+        } else if is_incoming_whisper(&message) && is_cef_heartbeat_reply_whisper(&message) {
+            // a heartbeat our sync source sent without us asking
+            let Ok(Some((_id, real_name))) = get_sender(&message) else {
+                continue;
+            };
+            if !heartbeat::is_subscribed_to(&real_name) {
+                continue;
+            }
+
+            SHOULD_BLOCK.set(true);
+
+            let encoded = read_encoded(&message, "!CEFT!").await;
+            match encoding::decode::<heartbeat::Heartbeat>(encoded) {
+                Ok(message) => heartbeat::received_message(message, heartbeat::MAX_ELAPSED),
+
+                Err(e) => {
+                    warn!("heartbeat from {}: {}", real_name, e);
+                }
+            }
         }
     }
 }
@@ -43,14 +66,15 @@ thread_local!(
     static SENDING: FutureShared<()> = FutureShared::new(());
 );
 
-async fn handle_request(message: String) -> Result<()> {
+/// entity id and real name of who whispered `message`
+fn get_sender(message: &str) -> Result<Option<(u8, String)>> {
     // "&9[>] "
     let colon_pos = message.find(": ").chain_err(|| "couldn't find colon")?;
     let nick_name = message.get(6..colon_pos).chain_err(|| "char boundary")?;
     info!("from {:?}", nick_name);
 
     // find real nick
-    let maybe_real_name = TAB_LIST
+    TAB_LIST
         .with_inner(|tab_list| {
             tab_list
                 .find_entry_by_nick_name(nick_name)
@@ -62,7 +86,7 @@ async fn handle_request(message: String) -> Result<()> {
                     ENTITIES
                         .with_inner(|entities| {
                             if entities.get(id).is_some() {
-                                Some(entry.get_real_name())
+                                Some((id, entry.get_real_name()))
                             } else {
                                 None
                             }
@@ -71,13 +95,29 @@ async fn handle_request(message: String) -> Result<()> {
                         .ok()?
                 })
         })
-        .chain_err(|| "TAB_LIST")?;
+        .chain_err(|| "TAB_LIST")
+}
+
+async fn handle_request(message: String) -> Result<()> {
+    let heartbeat = is_cef_heartbeat_request_whisper(&message);
+    if !heartbeat {
+        let version = get_cef_request_schema_version(&message);
+        ensure!(
+            version >= encoding::MIN_SCHEMA_VERSION,
+            "can only read schema version {}, not replying",
+            version
+        );
+    }
+
+    if let Some((id, real_name)) = get_sender(&message)? {
+        if heartbeat {
+            heartbeat::add_follower(real_name.clone(), id);
+        }
 
-    if let Some(real_name) = maybe_real_name {
         let mut mutex = SENDING.with(Clone::clone);
         let mutex = mutex.lock().await;
 
-        send_reply(real_name).await?;
+        send_reply(&real_name, heartbeat).await?;
 
         // don't trigger spam mute
         async_manager::sleep(Duration::from_secs(2)).await;
@@ -88,28 +128,52 @@ async fn handle_request(message: String) -> Result<()> {
     Ok(())
 }
 
-async fn send_reply(real_name: String) -> Result<()> {
+/// whisper a heartbeat to a player following our screens
+pub async fn send_heartbeat(real_name: &str) -> Result<()> {
+    let mut mutex = SENDING.with(Clone::clone);
+    let mutex = mutex.lock().await;
+
+    send_reply(real_name, true).await?;
+
+    // don't trigger spam mute
+    async_manager::sleep(Duration::from_secs(2)).await;
+
+    drop(mutex);
+
+    Ok(())
+}
+
+async fn send_reply(real_name: &str, heartbeat: bool) -> Result<()> {
     debug!("sending to {:?}", real_name);
 
-    let message = encoding::create_message();
+    let (reply_marker, encoded) = if heartbeat {
+        let message = heartbeat::create_message();
+        if message.screens.is_empty() {
+            debug!("no screens to send, not responding");
+            return Ok(());
+        }
 
-    if message.entities.is_empty() {
-        // don't send anything if nothing to send, asker will time out and ask someone else
-        debug!("no entities to send, not responding");
-        return Ok(());
-    }
+        ("!CEFT!", encoding::encode(&message)?)
+    } else {
+        let message = encoding::create_message();
+        if message.entities.is_empty() {
+            // don't send anything if nothing to send, asker will time out and ask someone else
+            debug!("no entities to send, not responding");
+            return Ok(());
+        }
 
-    let encoded = encoding::encode(&message)?;
+        ("!CEF!", encoding::encode(&message)?)
+    };
     debug!("sending encoded message length {}", encoded.len());
 
     // my outgoing info reply whisper
     async_manager::timeout(Duration::from_secs(5), async {
-        Chat::send(format!("@{real_name} !CEF!{encoded}"));
+        Chat::send(format!("@{real_name} {reply_marker}{encoded}"));
 
         loop {
             let message = wait_for_message().await;
 
-            if is_outgoing_whisper(&message) && is_cef_reply_whisper(&message) {
+            if is_outgoing_whisper(&message) && message.contains(reply_marker) {
                 SHOULD_BLOCK.set(true);
 
                 // also block > continuation messages
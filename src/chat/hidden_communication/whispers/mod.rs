@@ -1,15 +1,18 @@
 pub mod incoming;
 pub mod outgoing;
 
-use std::cell::Cell;
+use std::{cell::Cell, time::Duration};
 
 use classicube_helpers::{async_manager, WithInner};
 use futures::{future::RemoteHandle, prelude::*};
 use rand::seq::SliceRandom;
 use tracing::{debug, warn};
 
-use super::{encoding, wait_for_message, SHOULD_BLOCK};
-use crate::{chat::ENTITIES, error::Result};
+use super::{encoding, heartbeat, wait_for_message, SHOULD_BLOCK};
+use crate::{
+    chat::{is_continuation_message, ENTITIES},
+    error::Result,
+};
 
 thread_local!(
     static LISTENER: Cell<Option<RemoteHandle<()>>> = Cell::default();
@@ -51,6 +54,37 @@ pub async fn start_whispering(players: Vec<(u8, String)>) -> Result<()> {
     Ok(())
 }
 
+/// the encoded part of `message` after `marker`, joined with the
+/// continuation messages that follow it
+async fn read_encoded(message: &str, marker: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    let first_parts = message.splitn(2, marker).collect::<Vec<_>>();
+    let first_encoded = first_parts.get(1).copied().unwrap_or_default().to_string();
+    parts.push(first_encoded);
+
+    let timeout_result = async_manager::timeout(Duration::from_secs(1), async {
+        loop {
+            let message = wait_for_message().await;
+            if let Some(continuation) = is_continuation_message(&message) {
+                SHOULD_BLOCK.set(true);
+
+                parts.push(continuation.to_string());
+            } else {
+                debug!("stopping because of other message {:?}", message);
+                break;
+            }
+        }
+    })
+    .await;
+
+    if timeout_result.is_none() {
+        debug!("stopping because of timeout");
+    }
+
+    parts.join("")
+}
+
 pub fn start_listening() {
     let (f, remote_handle) = async {
         incoming::listen_loop().await;
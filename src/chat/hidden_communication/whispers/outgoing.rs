@@ -1,25 +1,79 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use classicube_helpers::async_manager;
 use tracing::debug;
 
-use super::{encoding, wait_for_message, SHOULD_BLOCK};
+use super::{encoding, heartbeat, read_encoded, wait_for_message, SHOULD_BLOCK};
 use crate::{
     chat::{
         helpers::{
-            is_cef_reply_whisper, is_cef_request_whisper, is_incoming_whisper, is_outgoing_whisper,
+            is_cef_heartbeat_reply_whisper, is_cef_heartbeat_request_whisper, is_cef_reply_whisper,
+            is_cef_request_whisper, is_incoming_whisper, is_outgoing_whisper,
         },
-        is_continuation_message, Chat,
+        Chat,
     },
-    error::{Error, Result, ResultExt},
+    error::{Result, ResultExt},
 };
 
 pub async fn query_whisper(real_name: &str) -> Result<bool> {
     debug!("query_whisper asking {}", real_name);
+    let asked_at = Instant::now();
 
+    // tell them which schema version we can read
+    let full_message_encoded = request_whisper(
+        real_name,
+        &format!("?CEF?{}", encoding::SCHEMA_VERSION),
+        "!CEF!",
+        is_cef_request_whisper,
+        is_cef_reply_whisper,
+    )
+    .await?;
+
+    debug!("got encoded message length {}", full_message_encoded.len());
+    let message: encoding::Message = encoding::decode(full_message_encoded)?;
+    debug!("decoded {:#?}", message);
+    let had_data = encoding::received_message(message, real_name, asked_at.elapsed()).await?;
+
+    if had_data {
+        heartbeat::set_sync_source(Some(real_name.to_string()));
+    }
+
+    Ok(had_data)
+}
+
+/// also asks them to keep sending us heartbeats
+pub async fn query_heartbeat(real_name: &str) -> Result<()> {
+    debug!("query_heartbeat asking {}", real_name);
+    let asked_at = Instant::now();
+
+    let encoded = request_whisper(
+        real_name,
+        "?CEFT?",
+        "!CEFT!",
+        is_cef_heartbeat_request_whisper,
+        is_cef_heartbeat_reply_whisper,
+    )
+    .await?;
+
+    let message: heartbeat::Heartbeat = encoding::decode(encoded)?;
+    debug!("decoded {:#?}", message);
+    heartbeat::received_message(message, asked_at.elapsed());
+
+    Ok(())
+}
+
+/// whisper `request` and wait for their reply starting with `reply_marker`,
+/// returns the encoded part of the reply
+async fn request_whisper(
+    real_name: &str,
+    request: &str,
+    reply_marker: &str,
+    is_request: fn(&str) -> bool,
+    is_reply: fn(&str) -> bool,
+) -> Result<String> {
     // my outgoing info request whisper
     async_manager::timeout(Duration::from_secs(3), async {
-        Chat::send(format!("@{real_name} ?CEF?"));
+        Chat::send(format!("@{real_name} {request}"));
         // SpiralP2 -> SpiralP
         // &7[<] &uSpiralP2: &f?CEF?
         // &9[>] &uSpiralP: &f?CEF?
@@ -27,7 +81,7 @@ pub async fn query_whisper(real_name: &str) -> Result<bool> {
         loop {
             let message = wait_for_message().await;
 
-            if is_outgoing_whisper(&message) && is_cef_request_whisper(&message) {
+            if is_outgoing_whisper(&message) && is_request(&message) {
                 SHOULD_BLOCK.set(true);
                 break;
             }
@@ -40,46 +94,16 @@ pub async fn query_whisper(real_name: &str) -> Result<bool> {
     let full_message_encoded = async_manager::timeout(Duration::from_secs(5), async {
         loop {
             let message = wait_for_message().await;
-            if is_incoming_whisper(&message) && is_cef_reply_whisper(&message) {
+            if is_incoming_whisper(&message) && is_reply(&message) {
                 SHOULD_BLOCK.set(true);
                 debug!("got whisper response {:?}", message);
 
-                let mut parts: Vec<String> = Vec::new();
-
-                let first_parts = message.splitn(2, "!CEF!").collect::<Vec<_>>();
-                let first_encoded = first_parts[1].to_string();
-                parts.push(first_encoded);
-
-                let timeout_result = async_manager::timeout(Duration::from_secs(1), async {
-                    loop {
-                        let message = wait_for_message().await;
-                        if let Some(continuation) = is_continuation_message(&message) {
-                            SHOULD_BLOCK.set(true);
-
-                            parts.push(continuation.to_string());
-                        } else {
-                            debug!("stopping because of other message {:?}", message);
-                            break;
-                        }
-                    }
-                })
-                .await;
-
-                if timeout_result.is_none() {
-                    debug!("stopping because of timeout");
-                }
-
-                let encoded: String = parts.join("");
-
-                break Ok::<String, Error>(encoded);
+                break read_encoded(&message, reply_marker).await;
             }
         }
     })
     .await
-    .chain_err(|| "never found response to my whisper")??;
+    .chain_err(|| "never found response to my whisper")?;
 
-    debug!("got encoded message length {}", full_message_encoded.len());
-    let message = encoding::decode(full_message_encoded)?;
-    debug!("decoded {:#?}", message);
-    encoding::received_message(message).await
+    Ok(full_message_encoded)
 }
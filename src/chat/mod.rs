@@ -80,6 +80,7 @@ impl Chat {
             *tab_list = Some(Entities::new());
         });
 
+        commands::initialize();
         hidden_communication::initialize();
     }
 
@@ -210,6 +211,24 @@ impl Chat {
         }
     }
 
+    /// Show a line in the top right status area, an empty string clears it.
+    pub fn print_status<S: Into<String>>(s: S) {
+        let s = s.into();
+        debug!("status {:?}", s);
+
+        #[cfg(not(test))]
+        {
+            use classicube_sys::{Chat_AddOf, MsgType_MSG_TYPE_STATUS_3, OwnedString};
+
+            let s = deunicode(&s);
+            let owned_string = OwnedString::new(s);
+
+            unsafe {
+                Chat_AddOf(owned_string.as_cc_string(), MsgType_MSG_TYPE_STATUS_3 as _);
+            }
+        }
+    }
+
     pub fn send<S: Into<String>>(s: S) {
         let s = s.into();
         info!("{}", s);
@@ -295,7 +314,11 @@ fn handle_chat_received(message: String, message_type: MsgType) {
                 opt = opt2;
             }
 
-            if let Some(player_snapshot) = opt {
+            if let Some(mut player_snapshot) = opt {
+                if !real_name.is_empty() {
+                    player_snapshot.real_name = Some(real_name);
+                }
+
                 FUTURE_HANDLE.with(|cell| {
                     let (remote, remote_handle) = async move {
                         if unsafe { Server.IsSinglePlayer } == 0 {
@@ -346,6 +369,10 @@ pub struct PlayerSnapshot {
     pub RotX: f32,
     pub RotY: f32,
     pub RotZ: f32,
+    /// who typed the command in chat, None for the server or `/client`
+    pub real_name: Option<String>,
+    /// typed with `/client cef`, which only runs on our client
+    pub is_client_command: bool,
 }
 
 impl PlayerSnapshot {
@@ -368,6 +395,8 @@ impl PlayerSnapshot {
                 RotX: rot[0],
                 RotY: rot[1],
                 RotZ: rot[2],
+                real_name: None,
+                is_client_command: false,
             })
         })
     }
@@ -3,7 +3,7 @@ use std::os::raw::{c_int, c_void};
 use classicube_sys::Bitmap;
 use tracing::warn;
 
-use super::EntityManager;
+use super::{screenshot, EntityManager};
 use crate::cef::RustRefBrowser;
 
 /// This gets called from cef browser's OnPaint
@@ -16,6 +16,8 @@ pub extern "C" fn cef_paint_callback(
 ) {
     let browser_id = browser.get_identifier();
 
+    screenshot::on_paint(browser_id, new_pixels, new_width, new_height);
+
     if let Err(e) = EntityManager::with_by_browser_id(browser_id, |entity| {
         if entity.get_scale() != 0.0 {
             let part = Bitmap {
@@ -0,0 +1,140 @@
+//! nudges a synced screen towards the time its sync source reported
+
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use super::CefEntity;
+use crate::{
+    error::Result,
+    player::{PlaybackState, PlayerTrait},
+};
+
+/// drift we ignore
+const NUDGE_DRIFT: Duration = Duration::from_millis(150);
+
+/// drift we seek over instead of catching up slowly
+const SEEK_DRIFT: Duration = Duration::from_secs(2);
+
+/// how much faster or slower to play while catching up
+const NUDGE_AMOUNT: f32 = 0.05;
+
+#[derive(Debug)]
+pub struct SyncTarget {
+    /// the source's time at `at`
+    time: Duration,
+    at: Instant,
+
+    /// the speed we should be playing at
+    speed: f32,
+
+    /// what the source was doing, `time` only advances while playing
+    state: PlaybackState,
+
+    /// paused or resumed us to match `state`
+    state_applied: bool,
+
+    nudging: bool,
+}
+
+impl SyncTarget {
+    pub fn new(time: Duration, at: Instant, speed: f32, state: PlaybackState) -> Self {
+        Self {
+            time,
+            at,
+            speed,
+            state,
+            state_applied: false,
+            nudging: false,
+        }
+    }
+
+    fn expected_time(&self) -> Duration {
+        if self.state == PlaybackState::Playing {
+            self.time + self.at.elapsed().mul_f32(self.speed)
+        } else {
+            self.time
+        }
+    }
+}
+
+impl CefEntity {
+    /// `real_time` is the time the page just reported
+    pub fn correct_drift(&mut self, real_time: Duration) -> Result<()> {
+        let Some(target) = self.sync_target.as_mut() else {
+            return Ok(());
+        };
+
+        // an ended source is about to play its next item, leave ours alone
+        if !target.state_applied && target.state != PlaybackState::Ended {
+            target.state_applied = true;
+
+            let playing = target.state == PlaybackState::Playing;
+            let our_state = self.player.get_playback_state();
+            if our_state != PlaybackState::Ended && (our_state == PlaybackState::Playing) != playing
+            {
+                if let Some(browser) = self.browser.as_ref() {
+                    debug!("source is {:?}, following", target.state);
+                    self.player.set_playing(browser, playing)?;
+                }
+            }
+        }
+
+        let expected = target.expected_time();
+        let (drift, behind) = if expected > real_time {
+            (expected - real_time, true)
+        } else {
+            (real_time - expected, false)
+        };
+
+        if drift > SEEK_DRIFT {
+            debug!("drift {:?}, seeking to {:?}", drift, expected);
+            self.seek_to_target()?;
+        } else if drift > NUDGE_DRIFT {
+            if !target.nudging {
+                debug!("drift {:?}, nudging", drift);
+                target.nudging = true;
+
+                let rate = if behind {
+                    target.speed * (1.0 + NUDGE_AMOUNT)
+                } else {
+                    target.speed * (1.0 - NUDGE_AMOUNT)
+                };
+
+                // only the page's rate, so our speed stays what we sync to others
+                let nudged = self
+                    .browser
+                    .as_ref()
+                    .is_some_and(|browser| self.player.set_playback_rate(browser, rate).is_ok());
+                if !nudged {
+                    // twitch, embeds and soundcloud can't change speed
+                    debug!("can't nudge, seeking instead");
+                    self.seek_to_target()?;
+                }
+            }
+        } else {
+            // caught up, also undoes a nudge from before the last heartbeat
+            let speed = target.speed;
+            self.sync_target = None;
+
+            self.player.set_speed(self.browser.as_ref(), speed)?;
+        }
+
+        Ok(())
+    }
+
+    fn seek_to_target(&mut self) -> Result<()> {
+        let Some(target) = self.sync_target.take() else {
+            return Ok(());
+        };
+        let expected = target.expected_time();
+
+        let browser = self.browser.as_ref();
+        self.player.set_speed(browser, target.speed)?;
+        if let Some(browser) = browser {
+            self.player.set_current_time(browser, expected)?;
+        }
+
+        Ok(())
+    }
+}
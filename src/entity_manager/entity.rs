@@ -1,11 +1,13 @@
 use std::{
     collections::VecDeque,
     mem,
+    ops::RangeInclusive,
     os::raw::c_short,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
+use clap::ValueEnum;
 use classicube_helpers::{async_manager, color::SILVER};
 use classicube_sys::{
     cc_int16, Bitmap, Entity, EntityVTABLE, Entity_Init, Entity_SetModel, Gfx_UpdateTexturePart,
@@ -13,19 +15,79 @@ use classicube_sys::{
     PACKEDCOL_WHITE,
 };
 use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tracing::{debug, warn};
 
-use super::{BROWSER_ID_TO_ENTITY_ID, TEXTURE_HEIGHT, TEXTURE_WIDTH};
+use super::{SyncTarget, BROWSER_ID_TO_ENTITY_ID, TEXTURE_HEIGHT, TEXTURE_WIDTH};
 use crate::{
     api,
-    cef::RustRefBrowser,
-    chat::Chat,
+    cef::{LoadError, RustRefBrowser},
+    chat::{Chat, PlayerSnapshot},
     entity_manager::{DEFAULT_MODEL_HEIGHT, DEFAULT_MODEL_WIDTH},
-    error::{Error, Result, ResultExt},
-    helpers::format_duration,
-    player::{Player, PlayerTrait, WebPlayer},
+    error::{bail, ensure, Error, Result, ResultExt},
+    helpers::{format_duration, seeded_shuffle},
+    player::{PlaybackState, Player, PlayerTrait, WebPlayer},
 };
 
+/// chromium's limits, 25% to 500%
+pub const ZOOM_RANGE: RangeInclusive<f32> = 0.25..=5.0;
+
+/// stylesheets are synced to everyone, so keep them short
+pub const USER_CSS_MAX_LENGTH: usize = 4096;
+
+/// what to do when the current item finishes playing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum RepeatMode {
+    /// play the next item in the queue
+    #[default]
+    Off,
+
+    /// play the current item again
+    One,
+
+    /// put the current item at the end of the queue
+    All,
+}
+
+/// who can control a screen besides its owner
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScreenAccess {
+    #[default]
+    Everyone,
+
+    /// nobody else
+    Owner,
+
+    /// these players, by their real names
+    Players(Vec<String>),
+}
+
+impl ScreenAccess {
+    /// Anyone can control unowned screens. `player_name` is None for commands
+    /// from the server, which we can't tell from a spoofed one.
+    pub fn allows(&self, owner: Option<&str>, player_name: Option<&str>) -> bool {
+        let Some(owner) = owner else {
+            return true;
+        };
+        let Some(player_name) = player_name else {
+            return false;
+        };
+
+        if owner.eq_ignore_ascii_case(player_name) {
+            return true;
+        }
+
+        match self {
+            Self::Everyone => true,
+            Self::Owner => false,
+            Self::Players(names) => names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(player_name)),
+        }
+    }
+}
+
 pub struct CefEntity {
     pub id: usize,
     pub name: Option<String>,
@@ -37,11 +99,24 @@ pub struct CefEntity {
     pub queue: VecDeque<(Player, Arc<Mutex<Option<String>>>)>,
     pub should_send: bool,
     pub background_color: u32,
+    pub repeat: RepeatMode,
+
+    /// 1.0 is 100%, applied on every page load
+    pub zoom: f32,
+    /// stylesheet added to every page load
+    pub user_css: Option<String>,
+
+    /// real name of who created it
+    pub owner: Option<String>,
+    pub access: ScreenAccess,
+
+    /// where the player we synced from was at, last we heard
+    pub sync_target: Option<SyncTarget>,
 
     v_table: Box<EntityVTABLE>,
     texture: OwnedGfxTexture,
 
-    page_loaded_senders: Vec<oneshot::Sender<()>>,
+    page_loaded_senders: Vec<oneshot::Sender<Result<(), LoadError>>>,
 }
 
 impl CefEntity {
@@ -83,13 +158,21 @@ impl CefEntity {
             texture,
             browser: None,
             player,
-            // TODO spawn lookups here?
             queue: queue
                 .drain(..)
-                .map(|player| (player, Arc::new(Mutex::new(None))))
+                .map(|player| {
+                    let title = lookup_title(&player, false);
+                    (player, title)
+                })
                 .collect(),
             should_send,
             background_color,
+            repeat: RepeatMode::Off,
+            zoom: 1.0,
+            user_css: None,
+            owner: None,
+            access: ScreenAccess::default(),
+            sync_target: None,
             page_loaded_senders: Vec::new(),
         };
 
@@ -207,6 +290,14 @@ impl CefEntity {
     /// if item was queued, returns the size of queue,
     /// else returns None meaning we're about to play the item
     pub fn queue(&mut self, player: Player) -> Result<Option<usize>> {
+        self.queue_at(self.queue.len(), player)
+    }
+
+    /// add item at index (starting at 0) in the queue
+    ///
+    /// if item was queued, returns its position in the queue,
+    /// else returns None meaning we're about to play the item
+    pub fn queue_at(&mut self, index: usize, player: Player) -> Result<Option<usize>> {
         // this needs to determine if the current player was finished,
         // if it was then we play right away,
         // else we queue it for next
@@ -216,53 +307,48 @@ impl CefEntity {
 
             Ok(None)
         } else {
-            let shared = Arc::new(Mutex::new(None));
-
-            // lookup title
-            if let Player::YouTube(yt) = &player {
-                let shared = shared.clone();
-                let youtube_id = yt.id.clone();
-
-                async_manager::spawn(async move {
-                    debug!("lookup {}", youtube_id);
-
-                    let f = async move {
-                        let response = async_manager::timeout(
-                            Duration::from_secs(5),
-                            api::youtube::video(&youtube_id),
-                        )
-                        .await
-                        .chain_err(|| "timed out")??;
-
-                        // Justice - Cross (Full Album) (49:21)
-                        let title = format!(
-                            "{} ({})",
-                            response.title,
-                            format_duration(Duration::from_secs(response.duration_seconds as _))
-                        );
-
-                        let mut shared = shared.lock().unwrap();
-                        *shared = Some(title.clone());
-
-                        async_manager::spawn_on_main_thread(async move {
-                            Chat::print(format!("{SILVER}{title}"));
-                        });
-
-                        Ok::<_, Error>(())
-                    };
-
-                    if let Err(e) = f.await {
-                        warn!("youtube lookup error: {}", e);
-                    }
-                });
-            }
-
-            self.queue.push_back((player, shared));
+            let title = lookup_title(&player, true);
+            let index = index.min(self.queue.len());
+            self.queue.insert(index, (player, title));
 
-            Ok(Some(self.queue.len()))
+            Ok(Some(index + 1))
         }
     }
 
+    /// remove item at index (starting at 0) from queue
+    pub fn queue_remove(&mut self, index: usize) -> Result<Player> {
+        let (player, _title) = self
+            .queue
+            .remove(index)
+            .chain_err(|| format!("no item {} in queue", index + 1))?;
+
+        Ok(player)
+    }
+
+    /// move item at index `from` to index `to` (starting at 0)
+    pub fn queue_move(&mut self, from: usize, to: usize) -> Result<()> {
+        ensure!(to < self.queue.len(), "no item {} in queue", to + 1);
+        let item = self
+            .queue
+            .remove(from)
+            .chain_err(|| format!("no item {} in queue", from + 1))?;
+        self.queue.insert(to, item);
+
+        Ok(())
+    }
+
+    /// shuffle with a seed so that everyone running the command gets the same order
+    pub fn queue_shuffle(&mut self, seed: u64) {
+        seeded_shuffle(self.queue.make_contiguous(), seed);
+    }
+
+    /// returns number of items removed
+    pub fn queue_clear(&mut self) -> usize {
+        let len = self.queue.len();
+        self.queue.clear();
+        len
+    }
+
     pub fn stop(&mut self) -> Result<()> {
         self.play(Player::Web(WebPlayer::blank_page()))
     }
@@ -273,6 +359,7 @@ impl CefEntity {
         // TODO move this into the Player enum's on_create
 
         let browser = self.browser.as_ref().chain_err(|| "no browser")?;
+        self.sync_target = None;
 
         if self.player.type_name() == player.type_name() {
             // try to persist volume options
@@ -306,6 +393,23 @@ impl CefEntity {
         Ok(())
     }
 
+    /// play the current item again or the next item depending on `repeat`
+    pub fn on_finished_playing(&mut self) -> Result<()> {
+        match self.repeat {
+            RepeatMode::Off => self.skip(),
+
+            RepeatMode::One => self.play(replay_of(&self.player)),
+
+            RepeatMode::All => {
+                let player = replay_of(&self.player);
+                let title = lookup_title(&player, false);
+                self.queue.push_back((player, title));
+
+                self.skip()
+            }
+        }
+    }
+
     pub fn attach_browser(&mut self, browser: RustRefBrowser) {
         let browser_id = browser.get_identifier();
 
@@ -318,17 +422,309 @@ impl CefEntity {
     }
 
     pub fn on_page_loaded(&mut self, browser: &RustRefBrowser) {
+        if let Err(e) = self.apply_page_style(browser) {
+            warn!("apply_page_style {}: {}", self.id, e);
+        }
+
         self.player.on_page_loaded(self.id, browser);
 
         for sender in self.page_loaded_senders.drain(..) {
-            let _ignore = sender.send(());
+            let _ignore = sender.send(Ok(()));
         }
     }
 
-    pub fn wait_for_page_load(&mut self) -> oneshot::Receiver<()> {
+    /// Status our player pages post from `postStatus()`.
+    pub fn on_page_message(&mut self, message: &Value) -> Result<()> {
+        let status = PageStatus::deserialize(message)?;
+
+        let time = status
+            .time
+            .and_then(|seconds| Duration::try_from_secs_f32(seconds).ok());
+        let state = status.state.as_deref().and_then(PlaybackState::from_js);
+        // drift correction changes speed on purpose
+        let speed = status.speed.filter(|_| self.sync_target.is_none());
+        let was_finished = self.player.is_finished_playing();
+
+        // for when someone syncs to us
+        match &mut self.player {
+            Player::Media(player) => {
+                if let Some(state) = state {
+                    player.state = state;
+                }
+                if let Some(speed) = speed {
+                    player.speed = speed;
+                }
+                if let Some(time) = time {
+                    player.time = time;
+                }
+                player.finished |= status.finished;
+            }
+            Player::YouTube(player) => {
+                if let Some(state) = state {
+                    player.state = state;
+                }
+                if let Some(speed) = speed {
+                    player.speed = speed;
+                }
+                if let Some(time) = time {
+                    player.time = time;
+                }
+                player.finished |= status.finished;
+            }
+            Player::Twitch(player) => {
+                if let Some(state) = state {
+                    player.state = state;
+                }
+                if let Some(time) = time {
+                    player.time = time;
+                }
+                player.finished |= status.finished;
+            }
+            Player::Embed(player) => {
+                if let Some(state) = state {
+                    player.state = state;
+                }
+                if let Some(time) = time {
+                    player.time = time;
+                }
+                player.finished |= status.finished;
+            }
+            Player::SoundCloud(player) => {
+                if let Some(state) = state {
+                    player.state = state;
+                }
+                if let Some(time) = time {
+                    player.time = time;
+                }
+                player.finished |= status.finished;
+            }
+
+            _ => {
+                bail!("{} pages don't post their status", self.player.type_name());
+            }
+        }
+
+        // for when we sync to someone else
+        if let Some(time) = time {
+            if let Err(e) = self.correct_drift(time) {
+                warn!("correcting drift: {}", e);
+            }
+        }
+
+        if status.finished && !was_finished {
+            debug!("finished playing!");
+            self.on_finished_playing()?;
+        }
+
+        Ok(())
+    }
+
+    /// Zoom and user stylesheet, chromium forgets the zoom when navigating to
+    /// another site.
+    pub fn apply_page_style(&self, browser: &RustRefBrowser) -> Result<()> {
+        browser.set_zoom(self.zoom)?;
+
+        if self.user_css.is_some() {
+            browser.execute_javascript(user_css_script(self.user_css.as_deref())?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) -> Result<()> {
+        ensure!(
+            ZOOM_RANGE.contains(&zoom),
+            "zoom must be between 25 and 500 percent"
+        );
+        self.zoom = zoom;
+
+        if let Some(browser) = &self.browser {
+            browser.set_zoom(zoom)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_user_css(&mut self, user_css: Option<String>) -> Result<()> {
+        ensure!(
+            user_css
+                .as_ref()
+                .map_or(true, |css| css.len() <= USER_CSS_MAX_LENGTH),
+            "stylesheet can't be longer than {} characters",
+            USER_CSS_MAX_LENGTH
+        );
+        self.user_css = user_css;
+
+        if let Some(browser) = &self.browser {
+            browser.execute_javascript(user_css_script(self.user_css.as_deref())?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn can_control(&self, player: &PlayerSnapshot) -> bool {
+        // only changes our own client
+        player.is_client_command
+            || self
+                .access
+                .allows(self.owner.as_deref(), player.real_name.as_deref())
+    }
+
+    pub fn ensure_can_control(&self, player: &PlayerSnapshot) -> Result<()> {
+        ensure!(
+            self.can_control(player),
+            "{} doesn't let you control this screen",
+            self.owner.as_deref().unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    /// Returns false if nobody was waiting to hear about it.
+    pub fn on_load_error(&mut self, error: &LoadError) -> bool {
+        let waiting = !self.page_loaded_senders.is_empty();
+
+        for sender in self.page_loaded_senders.drain(..) {
+            let _ignore = sender.send(Err(error.clone()));
+        }
+
+        waiting
+    }
+
+    pub fn wait_for_page_load(&mut self) -> oneshot::Receiver<Result<(), LoadError>> {
         let (sender, receiver) = oneshot::channel();
         self.page_loaded_senders.push(sender);
 
         receiver
     }
 }
+
+/// from `postStatus()` in the player pages
+#[derive(Debug, Deserialize)]
+struct PageStatus {
+    /// only set on the first message after finishing
+    finished: bool,
+    /// seconds
+    time: Option<f32>,
+    state: Option<String>,
+    speed: Option<f32>,
+}
+
+/// adds, replaces or removes (if `None`) our style element
+fn user_css_script(user_css: Option<&str>) -> Result<String> {
+    let css = serde_json::to_string(&user_css)?;
+
+    Ok(format!(
+        r#"(function () {{
+  var css = {css};
+  var style = document.getElementById("cef-user-css");
+  if (css === null) {{
+    if (style) style.remove();
+    return;
+  }}
+  if (!style) {{
+    style = document.createElement("style");
+    style.id = "cef-user-css";
+    (document.head || document.documentElement).appendChild(style);
+  }}
+  style.textContent = css;
+}})();"#
+    ))
+}
+
+/// same item but starting from the beginning
+fn replay_of(player: &Player) -> Player {
+    let mut player = player.clone();
+    match &mut player {
+        Player::YouTube(player) => {
+            player.time = Duration::ZERO;
+        }
+        Player::Media(player) => {
+            player.time = Duration::ZERO;
+        }
+        Player::Twitch(player) => {
+            player.time = Duration::ZERO;
+        }
+        Player::Embed(player) => {
+            player.time = Duration::ZERO;
+        }
+        Player::SoundCloud(player) => {
+            player.time = Duration::ZERO;
+        }
+
+        _ => {}
+    }
+
+    player
+}
+
+/// lookup title of a queued item in the background once when it's queued,
+/// printing it to chat when `print` is set
+///
+/// stays None if the lookup fails, lists show the url instead
+fn lookup_title(player: &Player, print: bool) -> Arc<Mutex<Option<String>>> {
+    let shared = Arc::new(Mutex::new(None));
+
+    if let Player::YouTube(yt) = player {
+        let shared = shared.clone();
+        let youtube_id = yt.id.clone();
+
+        async_manager::spawn(async move {
+            debug!("lookup {}", youtube_id);
+
+            let f = async move {
+                let response = async_manager::timeout(
+                    Duration::from_secs(5),
+                    api::youtube::video(&youtube_id),
+                )
+                .await
+                .chain_err(|| "timed out")??;
+
+                // Justice - Cross (Full Album) (49:21)
+                let title = format!(
+                    "{} ({})",
+                    response.title,
+                    format_duration(Duration::from_secs(response.duration_seconds as _))
+                );
+
+                let mut shared = shared.lock().unwrap();
+                *shared = Some(title.clone());
+
+                if print {
+                    async_manager::spawn_on_main_thread(async move {
+                        Chat::print(format!("{SILVER}{title}"));
+                    });
+                }
+
+                Ok::<_, Error>(())
+            };
+
+            if let Err(e) = f.await {
+                warn!("youtube lookup error: {}", e);
+            }
+        });
+    }
+
+    shared
+}
+
+#[test]
+fn test_screen_access() {
+    let dj = Some("DJ+");
+    let friend = Some("Friend");
+    let griefer = Some("Griefer");
+
+    assert!(ScreenAccess::Owner.allows(dj, Some("dj+")));
+    assert!(!ScreenAccess::Owner.allows(dj, griefer));
+    assert!(ScreenAccess::Everyone.allows(dj, griefer));
+
+    let players = ScreenAccess::Players(vec!["friend".to_string()]);
+    assert!(players.allows(dj, dj));
+    assert!(players.allows(dj, friend));
+    assert!(!players.allows(dj, griefer));
+
+    // unowned screens, and the server or a spoofed sender
+    assert!(ScreenAccess::Owner.allows(None, griefer));
+    assert!(ScreenAccess::Owner.allows(None, None));
+    assert!(!ScreenAccess::Owner.allows(dj, None));
+    assert!(!ScreenAccess::Everyone.allows(dj, None));
+}
@@ -1,8 +1,8 @@
 use std::collections::VecDeque;
 
-use tracing::debug;
+use tracing::{debug, warn};
 
-use super::{CefEntity, EntityManager, ENTITIES, NAME_TO_ID};
+use super::{CefEntity, EntityManager, RepeatMode, ScreenAccess, ENTITIES, NAME_TO_ID, ZOOM_RANGE};
 use crate::{
     cef::Cef,
     error::{Error, Result},
@@ -24,6 +24,11 @@ pub struct EntityBuilder {
     rotation: Option<(f32, f32)>,
     position: Option<(f32, f32, f32)>,
     background_color: Option<u32>,
+    repeat: RepeatMode,
+    zoom: f32,
+    user_css: Option<String>,
+    owner: Option<String>,
+    access: ScreenAccess,
 }
 
 impl EntityBuilder {
@@ -41,10 +46,31 @@ impl EntityBuilder {
             rotation: None,
             position: None,
             background_color: None,
+            repeat: RepeatMode::Off,
+            zoom: 1.0,
+            user_css: None,
+            owner: None,
+            access: ScreenAccess::default(),
         }
     }
 
     pub async fn create(mut self) -> Result<usize> {
+        // synced and saved embed urls are only checked, older peers don't send them
+        if let Player::Embed(embed) = &mut self.player {
+            embed.resolve().await?;
+        }
+        let mut queue = VecDeque::with_capacity(self.queue.len());
+        for mut player in self.queue.drain(..) {
+            if let Player::Embed(embed) = &mut player {
+                if let Err(e) = embed.resolve().await {
+                    warn!("dropping queued {}: {}", embed.url, e);
+                    continue;
+                }
+            }
+            queue.push_back(player);
+        }
+        self.queue = queue;
+
         let name = self.name.take();
         let url = self.player.on_create()?;
 
@@ -77,6 +103,12 @@ impl EntityBuilder {
                     entity.set_size(size.0, size.1);
                 }
                 entity.set_scale(self.scale);
+                entity.repeat = self.repeat;
+                // applied once the page loads
+                entity.zoom = self.zoom;
+                entity.user_css = self.user_css;
+                entity.owner = self.owner;
+                entity.access = self.access;
 
                 debug!("entity {} registered", entity_id);
                 entities.insert(entity_id, entity);
@@ -173,4 +205,34 @@ impl EntityBuilder {
         self.background_color = Some(background_color);
         self
     }
+
+    pub fn repeat(mut self, repeat: RepeatMode) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Clamped to `ZOOM_RANGE`, synced screens can have anything here.
+    pub fn zoom(mut self, zoom: f32) -> Self {
+        self.zoom = if zoom.is_finite() {
+            zoom.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end())
+        } else {
+            1.0
+        };
+        self
+    }
+
+    pub fn user_css(mut self, user_css: String) -> Self {
+        self.user_css = Some(user_css);
+        self
+    }
+
+    pub fn owner<S: Into<String>>(mut self, owner: S) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    pub fn access(mut self, access: ScreenAccess) -> Self {
+        self.access = access;
+        self
+    }
 }
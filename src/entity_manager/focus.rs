@@ -0,0 +1,277 @@
+//! Routes keyboard input to a screen while it's focused, by opening an
+//! invisible ClassiCube screen that grabs input.
+
+#![allow(non_upper_case_globals)]
+
+use std::{
+    cell::RefCell,
+    mem,
+    os::raw::{c_char, c_double, c_float, c_int, c_void},
+};
+
+use classicube_helpers::color::{SILVER, TEAL};
+use classicube_sys::{
+    cc_string, Convert_CP437ToUnicode, GuiPriority_GUI_PRIORITY_MENU, Gui_Add, Gui_Remove,
+    InputButtons, InputButtons_CCKEY_0, InputButtons_CCKEY_9, InputButtons_CCKEY_A,
+    InputButtons_CCKEY_BACKSLASH, InputButtons_CCKEY_BACKSPACE, InputButtons_CCKEY_COMMA,
+    InputButtons_CCKEY_DELETE, InputButtons_CCKEY_DOWN, InputButtons_CCKEY_END,
+    InputButtons_CCKEY_ENTER, InputButtons_CCKEY_EQUALS, InputButtons_CCKEY_ESCAPE,
+    InputButtons_CCKEY_F1, InputButtons_CCKEY_F24, InputButtons_CCKEY_HOME,
+    InputButtons_CCKEY_INSERT, InputButtons_CCKEY_LALT, InputButtons_CCKEY_LBRACKET,
+    InputButtons_CCKEY_LCTRL, InputButtons_CCKEY_LEFT, InputButtons_CCKEY_LSHIFT,
+    InputButtons_CCKEY_MINUS, InputButtons_CCKEY_PAGEDOWN, InputButtons_CCKEY_PAGEUP,
+    InputButtons_CCKEY_PERIOD, InputButtons_CCKEY_QUOTE, InputButtons_CCKEY_RALT,
+    InputButtons_CCKEY_RBRACKET, InputButtons_CCKEY_RCTRL, InputButtons_CCKEY_RIGHT,
+    InputButtons_CCKEY_RSHIFT, InputButtons_CCKEY_SEMICOLON, InputButtons_CCKEY_SLASH,
+    InputButtons_CCKEY_SPACE, InputButtons_CCKEY_TAB, InputButtons_CCKEY_TILDE,
+    InputButtons_CCKEY_UP, InputButtons_CCKEY_Z, Screen, ScreenVTABLE,
+};
+use tracing::warn;
+
+use super::EntityManager;
+use crate::{chat::Chat, error::Result};
+
+// cef_key_event_type_t
+const KEYEVENT_RAWKEYDOWN: c_int = 0;
+const KEYEVENT_KEYUP: c_int = 2;
+const KEYEVENT_CHAR: c_int = 3;
+
+// cef_event_flags_t
+const EVENTFLAG_SHIFT_DOWN: u32 = 1 << 1;
+const EVENTFLAG_CONTROL_DOWN: u32 = 1 << 2;
+const EVENTFLAG_ALT_DOWN: u32 = 1 << 3;
+
+thread_local!(
+    static FOCUSED: RefCell<Option<FocusScreen>> = RefCell::default();
+);
+
+struct FocusScreen {
+    entity_id: usize,
+
+    /// `EVENTFLAG_*` of held modifier keys
+    modifiers: u32,
+
+    // boxed so the pointers given to ClassiCube don't move
+    screen: Box<Screen>,
+    _v_table: Box<ScreenVTABLE>,
+}
+
+/// Start sending key presses to this screen until Escape is pressed.
+pub fn focus(entity_id: usize) -> Result<()> {
+    EntityManager::get_browser_by_entity_id(entity_id)?;
+    unfocus();
+
+    let v_table = Box::new(ScreenVTABLE {
+        Init: Some(noop),
+        Update: Some(noop_delta),
+        Free: Some(noop),
+        Render: Some(noop_delta),
+        BuildMesh: Some(noop),
+        HandlesInputDown: Some(handles_input_down),
+        OnInputUp: Some(on_input_up),
+        HandlesKeyPress: Some(handles_key_press),
+        HandlesTextChanged: Some(handles_text_changed),
+        HandlesPointerDown: Some(handles_pointer),
+        OnPointerUp: Some(on_pointer_up),
+        HandlesPointerMove: Some(handles_pointer),
+        HandlesMouseScroll: Some(handles_mouse_scroll),
+        Layout: Some(noop),
+        ContextLost: Some(noop),
+        ContextRecreated: Some(noop),
+    });
+
+    let mut screen: Box<Screen> = Box::new(unsafe { mem::zeroed() });
+    screen.VTABLE = v_table.as_ref();
+    // stops movement and hotkeys, and frees the cursor
+    screen.grabsInput = 1;
+
+    let screen_ptr: *mut Screen = screen.as_mut();
+
+    FOCUSED.with(|cell| {
+        let focused = &mut *cell.borrow_mut();
+        *focused = Some(FocusScreen {
+            entity_id,
+            modifiers: 0,
+            screen,
+            _v_table: v_table,
+        });
+    });
+
+    unsafe {
+        Gui_Add(screen_ptr, GuiPriority_GUI_PRIORITY_MENU as _);
+    }
+
+    Chat::print(format!(
+        "{TEAL}Typing into screen {SILVER}{entity_id}{TEAL}, press Escape to stop"
+    ));
+
+    Ok(())
+}
+
+pub fn unfocus() {
+    // take it out first, Gui_Remove calls back into the screen
+    let focused = FOCUSED.with(|cell| cell.borrow_mut().take());
+
+    if let Some(mut focused) = focused {
+        unsafe {
+            Gui_Remove(focused.screen.as_mut());
+        }
+
+        Chat::print(format!(
+            "{TEAL}Stopped typing into screen {SILVER}{}",
+            focused.entity_id
+        ));
+    }
+}
+
+pub fn get_focused() -> Option<usize> {
+    FOCUSED.with(|cell| cell.borrow().as_ref().map(|focused| focused.entity_id))
+}
+
+fn modifier_flag(key: InputButtons) -> Option<u32> {
+    match key {
+        InputButtons_CCKEY_LSHIFT | InputButtons_CCKEY_RSHIFT => Some(EVENTFLAG_SHIFT_DOWN),
+        InputButtons_CCKEY_LCTRL | InputButtons_CCKEY_RCTRL => Some(EVENTFLAG_CONTROL_DOWN),
+        InputButtons_CCKEY_LALT | InputButtons_CCKEY_RALT => Some(EVENTFLAG_ALT_DOWN),
+        _ => None,
+    }
+}
+
+/// Windows virtual-key code that cef expects
+fn windows_key_code(key: InputButtons) -> Option<c_int> {
+    let code = match key {
+        InputButtons_CCKEY_A..=InputButtons_CCKEY_Z => 0x41 + (key - InputButtons_CCKEY_A),
+        InputButtons_CCKEY_0..=InputButtons_CCKEY_9 => 0x30 + (key - InputButtons_CCKEY_0),
+        InputButtons_CCKEY_F1..=InputButtons_CCKEY_F24 => 0x70 + (key - InputButtons_CCKEY_F1),
+
+        InputButtons_CCKEY_BACKSPACE => 0x08,
+        InputButtons_CCKEY_TAB => 0x09,
+        InputButtons_CCKEY_ENTER => 0x0D,
+        InputButtons_CCKEY_SPACE => 0x20,
+        InputButtons_CCKEY_PAGEUP => 0x21,
+        InputButtons_CCKEY_PAGEDOWN => 0x22,
+        InputButtons_CCKEY_END => 0x23,
+        InputButtons_CCKEY_HOME => 0x24,
+        InputButtons_CCKEY_LEFT => 0x25,
+        InputButtons_CCKEY_UP => 0x26,
+        InputButtons_CCKEY_RIGHT => 0x27,
+        InputButtons_CCKEY_DOWN => 0x28,
+        InputButtons_CCKEY_INSERT => 0x2D,
+        InputButtons_CCKEY_DELETE => 0x2E,
+
+        InputButtons_CCKEY_LSHIFT | InputButtons_CCKEY_RSHIFT => 0x10,
+        InputButtons_CCKEY_LCTRL | InputButtons_CCKEY_RCTRL => 0x11,
+        InputButtons_CCKEY_LALT | InputButtons_CCKEY_RALT => 0x12,
+
+        InputButtons_CCKEY_SEMICOLON => 0xBA,
+        InputButtons_CCKEY_EQUALS => 0xBB,
+        InputButtons_CCKEY_COMMA => 0xBC,
+        InputButtons_CCKEY_MINUS => 0xBD,
+        InputButtons_CCKEY_PERIOD => 0xBE,
+        InputButtons_CCKEY_SLASH => 0xBF,
+        InputButtons_CCKEY_TILDE => 0xC0,
+        InputButtons_CCKEY_LBRACKET => 0xDB,
+        InputButtons_CCKEY_BACKSLASH => 0xDC,
+        InputButtons_CCKEY_RBRACKET => 0xDD,
+        InputButtons_CCKEY_QUOTE => 0xDE,
+
+        _ => return None,
+    };
+
+    Some(code as c_int)
+}
+
+fn send_key(key_type: c_int, windows_key_code: c_int, character: u16) {
+    let Some((entity_id, modifiers)) = FOCUSED.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .map(|focused| (focused.entity_id, focused.modifiers))
+    }) else {
+        return;
+    };
+
+    match EntityManager::get_browser_by_entity_id(entity_id) {
+        Ok(browser) => {
+            if let Err(e) = browser.send_key(key_type, modifiers, windows_key_code, character) {
+                warn!("focus: {}", e);
+            }
+        }
+
+        Err(e) => {
+            // screen was removed
+            warn!("focus: {}", e);
+            unfocus();
+        }
+    }
+}
+
+fn set_modifier(key: InputButtons, down: bool) {
+    if let Some(flag) = modifier_flag(key) {
+        FOCUSED.with(|cell| {
+            if let Some(focused) = cell.borrow_mut().as_mut() {
+                if down {
+                    focused.modifiers |= flag;
+                } else {
+                    focused.modifiers &= !flag;
+                }
+            }
+        });
+    }
+}
+
+extern "C" fn handles_input_down(_elem: *mut c_void, key: c_int) -> c_int {
+    let key = key as InputButtons;
+
+    if key == InputButtons_CCKEY_ESCAPE {
+        unfocus();
+        return 1;
+    }
+
+    set_modifier(key, true);
+
+    if let Some(code) = windows_key_code(key) {
+        send_key(KEYEVENT_RAWKEYDOWN, code, 0);
+
+        // ClassiCube doesn't give us a key press for enter
+        if key == InputButtons_CCKEY_ENTER {
+            send_key(KEYEVENT_CHAR, code, u16::from(b'\r'));
+        }
+    }
+
+    1
+}
+
+extern "C" fn on_input_up(_elem: *mut c_void, key: c_int) {
+    let key = key as InputButtons;
+
+    if let Some(code) = windows_key_code(key) {
+        send_key(KEYEVENT_KEYUP, code, 0);
+    }
+
+    set_modifier(key, false);
+}
+
+extern "C" fn handles_key_press(_elem: *mut c_void, key_char: c_char) -> c_int {
+    let character = unsafe { Convert_CP437ToUnicode(key_char) };
+    send_key(KEYEVENT_CHAR, c_int::from(character), character);
+
+    1
+}
+
+extern "C" fn handles_text_changed(_elem: *mut c_void, _str: *const cc_string) -> c_int {
+    0
+}
+
+extern "C" fn handles_pointer(_elem: *mut c_void, _id: c_int, _x: c_int, _y: c_int) -> c_int {
+    0
+}
+
+extern "C" fn on_pointer_up(_elem: *mut c_void, _id: c_int, _x: c_int, _y: c_int) {}
+
+extern "C" fn handles_mouse_scroll(_elem: *mut c_void, _delta: c_float) -> c_int {
+    0
+}
+
+extern "C" fn noop(_elem: *mut c_void) {}
+
+extern "C" fn noop_delta(_elem: *mut c_void, _delta: c_double) {}
@@ -0,0 +1,229 @@
+//! screen layouts saved to disk per server, restored with `cef layout load`
+//! or automatically when joining the map they were saved on
+
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use classicube_helpers::async_manager;
+use classicube_sys::{Server, World};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::EntityManager;
+use crate::{
+    chat::hidden_communication::encoding::LightEntity,
+    error::{bail, Result, ResultExt},
+};
+
+const LAYOUTS_DIR: &str = "cef/layouts";
+
+/// Identifies a map by its dimensions, since servers don't tell us a map name;
+/// layouts are already kept per server address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapSignature {
+    width: i32,
+    height: i32,
+    length: i32,
+}
+
+impl MapSignature {
+    pub fn current() -> Option<Self> {
+        let (width, height, length) = unsafe {
+            if World.Blocks.is_null() || World.Volume <= 0 {
+                return None;
+            }
+
+            (World.Width, World.Height, World.Length)
+        };
+
+        Some(Self {
+            width,
+            height,
+            length,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Layout {
+    /// restore when joining the map in `map`
+    pub auto: bool,
+    pub map: Option<MapSignature>,
+    pub entities: Vec<LightEntity>,
+}
+
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn get_server_dir() -> PathBuf {
+    let server = unsafe {
+        if Server.IsSinglePlayer == 0 {
+            format!("{}_{}", Server.Address.to_string(), Server.Port)
+        } else {
+            "singleplayer".to_string()
+        }
+    };
+
+    Path::new(LAYOUTS_DIR).join(sanitize(&server))
+}
+
+fn get_layout_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || sanitize(name) != name || name.starts_with('.') {
+        bail!("invalid layout name {:?}", name);
+    }
+
+    Ok(get_server_dir().join(format!("{name}.json")))
+}
+
+/// Saves all synced screens, returns how many were saved.
+pub fn save(name: &str, auto: bool) -> Result<usize> {
+    let path = get_layout_path(name)?;
+
+    let entities: Vec<_> = EntityManager::with_all_entities(|entities| {
+        entities
+            .values()
+            .filter(|entity| entity.should_send)
+            .map(LightEntity::from_entity)
+            .collect()
+    });
+    let count = entities.len();
+
+    let layout = Layout {
+        auto,
+        map: MapSignature::current(),
+        entities,
+    };
+
+    fs::create_dir_all(get_server_dir())?;
+    fs::write(&path, serde_json::to_vec_pretty(&layout)?)?;
+    debug!("saved layout {:?}", path);
+
+    Ok(count)
+}
+
+fn read(path: &Path) -> Result<Layout> {
+    let data = fs::read(path).chain_err(|| format!("couldn't read layout {path:?}"))?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Creates every screen in the layout, replacing screens with the same name.
+/// Returns how many were created.
+pub async fn load(name: &str) -> Result<usize> {
+    let layout = read(&get_layout_path(name)?)?;
+    restore(layout).await
+}
+
+async fn restore(layout: Layout) -> Result<usize> {
+    let mut count = 0;
+    for info in layout.entities {
+        if let Some(name) = info.name() {
+            if let Ok(entity_id) = EntityManager::with_entity(name, |entity| Ok(entity.id)) {
+                EntityManager::remove_entity(entity_id).await?;
+            }
+        }
+
+        info.into_builder().create().await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    let path = get_layout_path(name)?;
+    fs::remove_file(&path).chain_err(|| format!("couldn't delete layout {name:?}"))?;
+
+    Ok(())
+}
+
+fn read_all() -> Result<Vec<(String, Layout)>> {
+    let dir = get_server_dir();
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut layouts = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(OsStr::to_str) != Some("json") {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().and_then(OsStr::to_str) else {
+            continue;
+        };
+
+        match read(&path) {
+            Ok(layout) => layouts.push((name.to_string(), layout)),
+            Err(e) => warn!("layout {:?}: {}", path, e),
+        }
+    }
+    layouts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(layouts)
+}
+
+/// (name, auto) of every layout saved for this server
+pub fn list() -> Result<Vec<(String, bool)>> {
+    Ok(read_all()?
+        .into_iter()
+        .map(|(name, layout)| (name, layout.auto))
+        .collect())
+}
+
+pub fn on_new_map_loaded() {
+    let layouts: Vec<Layout> = match read_all() {
+        Ok(layouts) => layouts
+            .into_iter()
+            .map(|(_, layout)| layout)
+            .filter(|layout| layout.auto)
+            .collect(),
+        Err(e) => {
+            warn!("reading layouts: {}", e);
+            return;
+        }
+    };
+
+    if layouts.is_empty() {
+        return;
+    }
+
+    // only hash the map when there's something to compare it to
+    let Some(signature) = MapSignature::current() else {
+        return;
+    };
+
+    for layout in layouts {
+        if layout.map != Some(signature) {
+            continue;
+        }
+
+        debug!("auto-restoring layout for {:?}", signature);
+        async_manager::spawn_local_on_main_thread(async move {
+            if let Err(e) = restore(layout).await {
+                warn!("restoring layout: {}", e);
+            }
+        });
+    }
+}
+
+#[test]
+fn test_sanitize() {
+    assert_eq!(
+        sanitize("play.classicube.net_25565"),
+        "play.classicube.net_25565"
+    );
+    assert_eq!(sanitize("my map/../x"), "my_map_.._x");
+}
@@ -1,18 +1,27 @@
 mod cef_paint;
 mod context_handler;
+mod drift;
 mod entity;
 mod entity_builder;
+pub mod focus;
 mod helpers;
+pub mod layout;
 mod model;
+mod mouse;
 mod render_model_hook;
+pub mod screenshot;
 
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
     os::raw::c_int,
+    time::{Duration, Instant},
 };
 
-use classicube_helpers::async_manager;
+use classicube_helpers::{
+    async_manager,
+    color::{GOLD, RED, SILVER, TEAL, WHITE},
+};
 use classicube_sys::Vec3;
 use futures::{
     future::RemoteHandle,
@@ -21,12 +30,18 @@ use futures::{
 };
 use tracing::{debug, warn};
 
-pub use self::{cef_paint::cef_paint_callback, entity::CefEntity, entity_builder::EntityBuilder};
+pub use self::{
+    cef_paint::cef_paint_callback,
+    drift::SyncTarget,
+    entity::{CefEntity, RepeatMode, ScreenAccess, USER_CSS_MAX_LENGTH, ZOOM_RANGE},
+    entity_builder::EntityBuilder,
+};
 use self::{context_handler::ContextHandler, model::CefModel};
 use crate::{
-    cef::{Cef, CefEvent, RustRefBrowser},
-    error::{bail, Error, Result},
-    player::PlayerTrait,
+    cef::{self, Cef, CefEvent, DownloadEvent, DownloadState, RustRefBrowser},
+    chat::Chat,
+    error::{bail, ensure, Error, Result},
+    player::{Player, PlayerTrait, WebPlayer},
 };
 
 pub const TEXTURE_WIDTH: u16 = 2048;
@@ -52,6 +67,14 @@ thread_local!(
     static BROWSER_ID_TO_ENTITY_ID: RefCell<HashMap<c_int, usize>> = RefCell::default();
 );
 
+// screen that opened it, popup screen
+thread_local!(
+    static POPUPS: RefCell<HashMap<usize, usize>> = RefCell::default();
+);
+
+/// least time between popups from one screen, sooner ones are dropped
+const POPUP_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct EntityManager {
     // model is just the shape, the entities holds the texture id and scaling
     model: Option<CefModel>,
@@ -60,6 +83,8 @@ pub struct EntityManager {
 
     cef_event_page_loaded: Option<RemoteHandle<()>>,
     cef_event_title_change: Option<RemoteHandle<()>>,
+    cef_event_loading: Option<RemoteHandle<()>>,
+    cef_event_page_message: Option<RemoteHandle<()>>,
 }
 
 impl EntityManager {
@@ -69,6 +94,8 @@ impl EntityManager {
             context_handler: ContextHandler::new(),
             cef_event_page_loaded: None,
             cef_event_title_change: None,
+            cef_event_loading: None,
+            cef_event_page_message: None,
         }
     }
 
@@ -78,6 +105,7 @@ impl EntityManager {
         self.context_handler.initialize();
         render_model_hook::initialize();
         self.model = Some(CefModel::register());
+        mouse::initialize();
 
         self.initialize_listeners();
     }
@@ -120,6 +148,278 @@ impl EntityManager {
         .remote_handle();
         async_manager::spawn_local_on_main_thread(f);
         self.cef_event_title_change = Some(remote_handle);
+
+        let mut event_listener = Cef::create_event_listener();
+        let (f, remote_handle) = async move {
+            while let Ok(event) = event_listener.recv().await {
+                if let CefEvent::PageMessage(browser, message) = event {
+                    let browser_id = browser.get_identifier();
+
+                    if let Err(e) = EntityManager::with_by_browser_id(browser_id, |entity| {
+                        entity.on_page_message(&message)
+                    }) {
+                        warn!("{}", e);
+                    }
+                }
+            }
+        }
+        .remote_handle();
+        async_manager::spawn_local_on_main_thread(f);
+        self.cef_event_page_message = Some(remote_handle);
+
+        let mut event_listener = Cef::create_event_listener();
+        let (f, remote_handle) = async move {
+            // browser whose progress is in the status line
+            let mut status_browser_id = None;
+            // browser id, when it last opened a popup
+            let mut last_popups: HashMap<c_int, Instant> = HashMap::new();
+
+            while let Ok(event) = event_listener.recv().await {
+                match event {
+                    CefEvent::BrowserLoadStart(browser) => {
+                        Self::show_load_progress(&mut status_browser_id, &browser, 0.0);
+                    }
+
+                    CefEvent::BrowserLoadProgress(browser, progress) => {
+                        Self::show_load_progress(&mut status_browser_id, &browser, progress);
+                    }
+
+                    CefEvent::BrowserLoadError(browser, error) => {
+                        let browser_id = browser.get_identifier();
+                        if status_browser_id == Some(browser_id) {
+                            status_browser_id = None;
+                            Chat::print_status("");
+                        }
+
+                        if let Err(e) = EntityManager::with_by_browser_id(browser_id, |entity| {
+                            // "cef create" reports it itself
+                            if !entity.on_load_error(&error) {
+                                Chat::print(format!(
+                                    "{RED}Screen {} failed to load: {} {SILVER}{}",
+                                    entity.id, error, error.url
+                                ));
+                            }
+                            Ok(())
+                        }) {
+                            warn!("{}", e);
+                        }
+                    }
+
+                    CefEvent::BrowserNavigationBlocked(browser, reason) => {
+                        let browser_id = browser.get_identifier();
+                        if let Ok(entity_id) =
+                            EntityManager::with_by_browser_id(browser_id, |entity| Ok(entity.id))
+                        {
+                            let screen = Self::get_name_by_entity_id(entity_id)
+                                .unwrap_or_else(|| entity_id.to_string());
+                            Chat::print(format!(
+                                "{RED}Screen {SILVER}{screen} {RED}can't go there: {reason}"
+                            ));
+                        }
+                    }
+
+                    CefEvent::BrowserPopup(browser, url) => {
+                        // pages calling window.open in a loop
+                        let now = Instant::now();
+                        let last_popup = last_popups.insert(browser.get_identifier(), now);
+                        if last_popup.is_some_and(|last| now - last < POPUP_INTERVAL) {
+                            debug!("dropping popup {:?}", url);
+                            continue;
+                        }
+
+                        async_manager::spawn_local_on_main_thread(async move {
+                            if let Err(e) = Self::open_popup(&browser, &url).await {
+                                warn!("open_popup {:?}: {}", url, e);
+                                Chat::print(format!("{RED}Couldn't open popup: {e}"));
+                            }
+                        });
+                    }
+
+                    CefEvent::BrowserJsDialog(browser, kind, message) => {
+                        let browser_id = browser.get_identifier();
+                        match EntityManager::with_by_browser_id(browser_id, |entity| {
+                            Ok((entity.id, entity.get_scale() > 0.0))
+                        }) {
+                            // hidden screens like map themes, nobody can answer
+                            Ok((_entity_id, false)) => {
+                                debug!("dismissing {} on hidden screen: {:?}", kind, message);
+                                if let Err(e) = cef::respond_to_dialog(browser_id, false, None) {
+                                    warn!("{}", e);
+                                }
+                            }
+
+                            Ok((entity_id, true)) => {
+                                let name = Self::get_name_by_entity_id(entity_id);
+                                Chat::print(format!(
+                                    "{TEAL}Screen {SILVER}{} {TEAL}{kind}: {WHITE}{message}",
+                                    name.as_deref().unwrap_or(&entity_id.to_string())
+                                ));
+
+                                // unnamed screens are answered by aiming at them
+                                let target =
+                                    name.map(|name| format!("-n {name} ")).unwrap_or_default();
+                                Chat::print(format!(
+                                    "{TEAL}Answer with {SILVER}cef dialog {target}accept {TEAL}or \
+                                     {SILVER}dismiss {TEAL}[text]"
+                                ));
+                            }
+
+                            Err(e) => {
+                                warn!("{}", e);
+                            }
+                        }
+                    }
+
+                    CefEvent::BrowserDownload(browser, DownloadEvent { file_name, state }) => {
+                        let browser_id = browser.get_identifier();
+                        let Ok(entity_id) =
+                            EntityManager::with_by_browser_id(browser_id, |entity| Ok(entity.id))
+                        else {
+                            continue;
+                        };
+                        let screen = Self::get_name_by_entity_id(entity_id)
+                            .unwrap_or_else(|| entity_id.to_string());
+
+                        if !matches!(state, DownloadState::Progress(_))
+                            && status_browser_id == Some(browser_id)
+                        {
+                            status_browser_id = None;
+                            Chat::print_status("");
+                        }
+
+                        match state {
+                            DownloadState::Started => {
+                                Chat::print(format!(
+                                    "{TEAL}Screen {SILVER}{screen} {TEAL}downloading \
+                                     {WHITE}{file_name}"
+                                ));
+                            }
+
+                            DownloadState::Progress(percent) => {
+                                status_browser_id = Some(browser_id);
+                                Chat::print_status(format!(
+                                    "{TEAL}Downloading {WHITE}{file_name} {GOLD}{percent}%"
+                                ));
+                            }
+
+                            DownloadState::Complete(path) => {
+                                Chat::print(format!(
+                                    "{TEAL}Screen {SILVER}{screen} {TEAL}saved {WHITE}{}",
+                                    path.display()
+                                ));
+                            }
+
+                            DownloadState::Canceled(reason) => {
+                                Chat::print(format!(
+                                    "{RED}Screen {SILVER}{screen} {RED}download of \
+                                     {WHITE}{file_name} {RED}canceled: {reason}"
+                                ));
+                            }
+                        }
+                    }
+
+                    CefEvent::BrowserClosed(browser) => {
+                        last_popups.remove(&browser.get_identifier());
+                        if status_browser_id == Some(browser.get_identifier()) {
+                            status_browser_id = None;
+                            Chat::print_status("");
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+        .remote_handle();
+        async_manager::spawn_local_on_main_thread(f);
+        self.cef_event_loading = Some(remote_handle);
+    }
+
+    /// new screen just to the side of the one that opened it, or the popup
+    /// screen it opened before
+    async fn open_popup(parent_browser: &RustRefBrowser, url: &str) -> Result<usize> {
+        let player = Player::Web(WebPlayer::from_input(url)?);
+
+        let (parent_id, position, [rot_x, rot_y], scale, (width, height), background_color) =
+            EntityManager::with_by_browser_id(parent_browser.get_identifier(), |entity| {
+                Ok((
+                    entity.id,
+                    entity.entity.Position,
+                    [entity.entity.RotX, entity.entity.RotY],
+                    entity.get_scale(),
+                    entity.get_size(),
+                    entity.background_color,
+                ))
+            })?;
+
+        // one popup screen per screen, popups from it load in place
+        let popup_id = POPUPS.with(|cell| {
+            let popups = &*cell.borrow();
+            popups
+                .get(&parent_id)
+                .copied()
+                .or_else(|| popups.values().find(|&&id| id == parent_id).copied())
+        });
+        if let Some(popup_id) = popup_id {
+            if EntityManager::with_entity(popup_id, |_| Ok(())).is_ok() {
+                EntityManager::with_entity(popup_id, |entity| entity.play(player))?;
+                return Ok(popup_id);
+            }
+        }
+
+        let (resolution_width, resolution_height) = Cef::get_browser_size(parent_browser);
+
+        // screen's right, with a small gap between them
+        let yaw = rot_y.to_radians();
+        let offset = scale * f32::from(width) * 1.1;
+
+        let entity_id = EntityBuilder::new(player)
+            .should_send(false)
+            .position(
+                position.X + yaw.cos() * offset,
+                position.Y,
+                position.Z + yaw.sin() * offset,
+            )
+            .rotation(rot_x, rot_y)
+            .scale(scale)
+            .size(width, height)
+            .resolution(resolution_width, resolution_height)
+            .background_color(background_color)
+            .create()
+            .await?;
+
+        POPUPS.with(|cell| {
+            cell.borrow_mut().insert(parent_id, entity_id);
+        });
+
+        Ok(entity_id)
+    }
+
+    fn show_load_progress(
+        status_browser_id: &mut Option<c_int>,
+        browser: &RustRefBrowser,
+        progress: f64,
+    ) {
+        let browser_id = browser.get_identifier();
+
+        // hidden screens like map themes
+        let Ok(entity_id) = EntityManager::with_by_browser_id(browser_id, |entity| {
+            ensure!(entity.get_scale() > 0.0, "hidden screen");
+            Ok(entity.id)
+        }) else {
+            return;
+        };
+
+        if progress < 1.0 {
+            *status_browser_id = Some(browser_id);
+            Chat::print_status(format!(
+                "{TEAL}Loading screen {SILVER}{entity_id} {GOLD}{}%",
+                (progress * 100.0) as u8
+            ));
+        } else if *status_browser_id == Some(browser_id) {
+            *status_browser_id = None;
+            Chat::print_status("");
+        }
     }
 
     pub fn on_new_map_loaded(&mut self) {
@@ -133,11 +433,14 @@ impl EntityManager {
     pub fn shutdown(&mut self) {
         debug!("shutdown entity_manager");
 
+        mouse::shutdown();
         self.context_handler.shutdown();
         render_model_hook::shutdown();
         self.model.take();
         self.cef_event_page_loaded.take();
         self.cef_event_title_change.take();
+        self.cef_event_loading.take();
+        self.cef_event_page_message.take();
 
         async_manager::block_on_local(async {
             Self::remove_all_entities().await.unwrap();
@@ -181,6 +484,16 @@ impl EntityManager {
         })
     }
 
+    pub fn get_name_by_entity_id(entity_id: usize) -> Option<String> {
+        NAME_TO_ID.with(|cell| {
+            let name_to_id = &*cell.borrow();
+            name_to_id
+                .iter()
+                .find(|(_, &id)| id == entity_id)
+                .map(|(name, _)| name.clone())
+        })
+    }
+
     pub async fn remove_entity(entity_id: usize) -> Result<()> {
         NAME_TO_ID.with(|cell| {
             let name_to_id = &mut *cell.borrow_mut();
@@ -212,6 +525,16 @@ impl EntityManager {
             }
         })?;
 
+        if focus::get_focused() == Some(entity_id) {
+            focus::unfocus();
+        }
+
+        POPUPS.with(|cell| {
+            cell.borrow_mut().retain(|&parent_id, &mut popup_id| {
+                parent_id != entity_id && popup_id != entity_id
+            });
+        });
+
         if let Some(browser) = maybe_browser {
             EntityManager::on_browser_close(&browser);
 
@@ -0,0 +1,367 @@
+//! forwards hovering, clicks, dragging and scrolling to the screen under the
+//! crosshair when the mouse option is on
+//!
+//! The game handles the same clicks before we see them and plugins can't
+//! cancel them, so clicking a screen also breaks or places the block behind
+//! it. That's why the option is off by default.
+
+use std::{
+    cell::{Cell, RefCell},
+    os::raw::c_int,
+    time::{Duration, Instant},
+};
+
+use classicube_helpers::{
+    async_manager,
+    events::input::{DownEventHandler, UpEventHandler, WheelEventHandler},
+};
+use classicube_sys::{
+    Gui, InputButtons, InputButtons_CCMOUSE_L, InputButtons_CCMOUSE_M, InputButtons_CCMOUSE_R,
+    ENTITIES_SELF_ID,
+};
+use futures::{future::RemoteHandle, prelude::*};
+use tracing::warn;
+
+use super::EntityManager;
+use crate::{
+    cef::{Cef, RustRefBrowser},
+    chat::{commands::helpers::get_click_coords, PlayerSnapshot},
+    error::Result,
+    helpers::vec3_to_vector3,
+    options,
+};
+
+// cef_event_flags_t
+const EVENTFLAG_LEFT_MOUSE_BUTTON: u32 = 1 << 4;
+const EVENTFLAG_MIDDLE_MOUSE_BUTTON: u32 = 1 << 5;
+const EVENTFLAG_RIGHT_MOUSE_BUTTON: u32 = 1 << 6;
+
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+const DOUBLE_CLICK_DISTANCE: c_int = 4;
+
+// pixels per wheel notch
+const WHEEL_DELTA: f32 = 120.0;
+
+thread_local!(
+    static HANDLERS: RefCell<Option<(DownEventHandler, UpEventHandler, WheelEventHandler)>> =
+        RefCell::default();
+);
+
+thread_local!(
+    static LOOP_HANDLE: Cell<Option<RemoteHandle<()>>> = Cell::default();
+);
+
+thread_local!(
+    static STATE: RefCell<MouseState> = RefCell::default();
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    fn from_key(key: InputButtons) -> Option<Self> {
+        #[allow(non_upper_case_globals)]
+        match key {
+            InputButtons_CCMOUSE_L => Some(Self::Left),
+            InputButtons_CCMOUSE_M => Some(Self::Middle),
+            InputButtons_CCMOUSE_R => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    /// cef's `MouseButtonType`
+    fn to_cef(self) -> c_int {
+        match self {
+            Self::Left => 0,
+            Self::Middle => 1,
+            Self::Right => 2,
+        }
+    }
+
+    fn flag(self) -> u32 {
+        match self {
+            Self::Left => EVENTFLAG_LEFT_MOUSE_BUTTON,
+            Self::Middle => EVENTFLAG_MIDDLE_MOUSE_BUTTON,
+            Self::Right => EVENTFLAG_RIGHT_MOUSE_BUTTON,
+        }
+    }
+}
+
+/// browser pixel a screen is being looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Target {
+    entity_id: usize,
+    x: c_int,
+    y: c_int,
+}
+
+#[derive(Debug)]
+struct LastClick {
+    button: MouseButton,
+    at: Instant,
+    x: c_int,
+    y: c_int,
+    count: c_int,
+}
+
+#[derive(Debug, Default)]
+struct MouseState {
+    /// where the crosshair was on the last frame
+    hover: Option<Target>,
+
+    /// flags of held buttons, while any are held the pressed screen keeps
+    /// getting moves so sliders and selections can be dragged
+    buttons: u32,
+
+    last_click: Option<LastClick>,
+}
+
+pub fn initialize() {
+    let mut down_handler = DownEventHandler::new();
+    down_handler.on(|event| {
+        if !event.repeating {
+            on_button(event.key, false);
+        }
+    });
+
+    let mut up_handler = UpEventHandler::new();
+    up_handler.on(|event| {
+        on_button(event.key, true);
+    });
+
+    let mut wheel_handler = WheelEventHandler::new();
+    wheel_handler.on(|event| {
+        on_wheel(event.delta);
+    });
+
+    HANDLERS.with(|cell| {
+        let cell = &mut *cell.borrow_mut();
+        *cell = Some((down_handler, up_handler, wheel_handler));
+    });
+
+    let (f, remote_handle) = async {
+        loop {
+            update_hover();
+            async_manager::yield_now().await;
+        }
+    }
+    .remote_handle();
+    LOOP_HANDLE.set(Some(remote_handle));
+    async_manager::spawn_local_on_main_thread(f);
+}
+
+pub fn shutdown() {
+    LOOP_HANDLE.set(None);
+
+    HANDLERS.with(|cell| {
+        let cell = &mut *cell.borrow_mut();
+        *cell = None;
+    });
+
+    STATE.with(|cell| {
+        let state = &mut *cell.borrow_mut();
+        *state = MouseState::default();
+    });
+}
+
+fn is_enabled() -> bool {
+    // chat or a menu is open
+    let input_grabbed = !unsafe { Gui.InputGrab }.is_null();
+
+    !input_grabbed && options::MOUSE.get().unwrap_or(false)
+}
+
+/// closest screen under the crosshair
+fn find_target() -> Option<Target> {
+    let player = PlayerSnapshot::from_entity_id(ENTITIES_SELF_ID as _)?;
+    let eye_position = vec3_to_vector3(&player.eye_position);
+
+    EntityManager::with_all_entities(|entities| {
+        entities
+            .values()
+            .filter_map(|entity| {
+                let browser = entity.browser.as_ref()?;
+                let (browser_width, browser_height) = Cef::get_browser_size(browser);
+
+                let (x, y) = get_click_coords(
+                    player.eye_position,
+                    entity.entity.Position,
+                    player.Pitch,
+                    player.Yaw,
+                    entity.entity.RotX,
+                    entity.entity.RotY,
+                    entity.entity.ModelScale,
+                    entity.get_size(),
+                    browser_width as u32,
+                    browser_height as u32,
+                )
+                .ok()??;
+
+                let distance =
+                    (vec3_to_vector3(&entity.entity.Position) - eye_position).norm_squared();
+
+                Some((
+                    distance,
+                    Target {
+                        entity_id: entity.id,
+                        x: x as c_int,
+                        y: y as c_int,
+                    },
+                ))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, target)| target)
+    })
+}
+
+fn with_browser<F>(entity_id: usize, f: F)
+where
+    F: FnOnce(&RustRefBrowser) -> Result<()>,
+{
+    if let Err(e) =
+        EntityManager::get_browser_by_entity_id(entity_id).and_then(|browser| f(&browser))
+    {
+        warn!("mouse: {}", e);
+    }
+}
+
+fn update_hover() {
+    let target = if is_enabled() { find_target() } else { None };
+
+    STATE.with(|cell| {
+        let state = &mut *cell.borrow_mut();
+
+        if state.buttons != 0 {
+            // dragging, only follow the crosshair while it stays on the pressed screen
+            if let (Some(hover), Some(target)) = (state.hover, target) {
+                if hover.entity_id == target.entity_id && hover != target {
+                    with_browser(target.entity_id, |browser| {
+                        browser.send_mouse_move(target.x, target.y, state.buttons, false)
+                    });
+                    state.hover = Some(target);
+                }
+            }
+            return;
+        }
+
+        if state.hover == target {
+            return;
+        }
+
+        if let Some(hover) = state.hover {
+            if !matches!(target, Some(target) if target.entity_id == hover.entity_id) {
+                // the screen may have been removed already
+                if let Ok(browser) = EntityManager::get_browser_by_entity_id(hover.entity_id) {
+                    let _ignore = browser.send_mouse_move(hover.x, hover.y, 0, true);
+                }
+            }
+        }
+
+        if let Some(target) = target {
+            with_browser(target.entity_id, |browser| {
+                browser.send_mouse_move(target.x, target.y, 0, false)
+            });
+        }
+
+        state.hover = target;
+    });
+}
+
+fn on_button(key: InputButtons, mouse_up: bool) {
+    let Some(button) = MouseButton::from_key(key) else {
+        return;
+    };
+
+    STATE.with(|cell| {
+        let state = &mut *cell.borrow_mut();
+        let Some(hover) = state.hover else {
+            return;
+        };
+
+        if mouse_up {
+            // pressed somewhere we didn't see it
+            if state.buttons & button.flag() == 0 {
+                return;
+            }
+            state.buttons &= !button.flag();
+
+            let click_count = state
+                .last_click
+                .as_ref()
+                .filter(|last_click| last_click.button == button)
+                .map_or(1, |last_click| last_click.count);
+
+            with_browser(hover.entity_id, |browser| {
+                browser.send_mouse_button(
+                    hover.x,
+                    hover.y,
+                    state.buttons,
+                    button.to_cef(),
+                    true,
+                    click_count,
+                )
+            });
+        } else {
+            if state.buttons & button.flag() != 0 || !is_enabled() {
+                return;
+            }
+
+            let click_count = match &state.last_click {
+                Some(last_click)
+                    if last_click.button == button
+                        && last_click.at.elapsed() < DOUBLE_CLICK_TIME
+                        && (last_click.x - hover.x).abs() <= DOUBLE_CLICK_DISTANCE
+                        && (last_click.y - hover.y).abs() <= DOUBLE_CLICK_DISTANCE =>
+                {
+                    // triple click selects a paragraph, no need to count further
+                    (last_click.count + 1).min(3)
+                }
+                _ => 1,
+            };
+            state.last_click = Some(LastClick {
+                button,
+                at: Instant::now(),
+                x: hover.x,
+                y: hover.y,
+                count: click_count,
+            });
+
+            with_browser(hover.entity_id, |browser| {
+                browser.send_mouse_button(
+                    hover.x,
+                    hover.y,
+                    state.buttons,
+                    button.to_cef(),
+                    false,
+                    click_count,
+                )
+            });
+            state.buttons |= button.flag();
+        }
+    });
+}
+
+fn on_wheel(delta: f32) {
+    if !is_enabled() {
+        return;
+    }
+
+    STATE.with(|cell| {
+        let state = &*cell.borrow();
+        if let Some(hover) = state.hover {
+            with_browser(hover.entity_id, |browser| {
+                browser.send_mouse_wheel(
+                    hover.x,
+                    hover.y,
+                    state.buttons,
+                    0,
+                    (delta * WHEEL_DELTA) as c_int,
+                )
+            });
+        }
+    });
+}
@@ -0,0 +1,156 @@
+//! Saves what a screen shows as a png in ClassiCube's screenshots folder.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, File},
+    io::BufWriter,
+    os::raw::{c_int, c_void},
+    path::{Path, PathBuf},
+    slice,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use classicube_helpers::async_manager;
+use futures::channel::oneshot;
+use tracing::debug;
+
+use super::EntityManager;
+use crate::{
+    cef::Cef,
+    error::{Error, Result, ResultExt},
+};
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// cef can take a moment to paint a freshly resized screen
+const PAINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// browser identifier, waiting for the next paint
+thread_local!(
+    static WAITING: RefCell<HashMap<c_int, Vec<oneshot::Sender<Frame>>>> = RefCell::default();
+);
+
+struct Frame {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+/// Called for every paint, copies the pixels if a screenshot is waiting.
+pub fn on_paint(browser_id: c_int, pixels: *const c_void, width: c_int, height: c_int) {
+    let Some(senders) = WAITING.with(|cell| cell.borrow_mut().remove(&browser_id)) else {
+        return;
+    };
+
+    let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return;
+    };
+    let bgra =
+        unsafe { slice::from_raw_parts(pixels.cast::<u8>(), width as usize * height as usize * 4) };
+    let rgba = bgra_to_rgba(bgra);
+
+    for sender in senders {
+        let _ignore_error = sender.send(Frame {
+            width,
+            height,
+            rgba: rgba.clone(),
+        });
+    }
+}
+
+/// Save the screen's next paint, returns the path of the png.
+pub async fn take(entity_id: usize) -> Result<PathBuf> {
+    let browser = EntityManager::get_browser_by_entity_id(entity_id)?;
+    let browser_id = browser.get_identifier();
+
+    let frame = async_manager::timeout(PAINT_TIMEOUT, async {
+        loop {
+            let (sender, receiver) = oneshot::channel();
+            WAITING.with(|cell| {
+                let waiting = &mut *cell.borrow_mut();
+                waiting.entry(browser_id).or_default().push(sender);
+            });
+            browser.invalidate()?;
+
+            let frame = receiver.await?;
+
+            // a paint from before "cef resolution" finished
+            let (width, height) = Cef::get_browser_size(&browser);
+            if frame.width == u32::from(width) && frame.height == u32::from(height) {
+                return Ok::<_, Error>(frame);
+            }
+            debug!(
+                "screenshot got {}x{} paint, waiting for {}x{}",
+                frame.width, frame.height, width, height
+            );
+        }
+    })
+    .await
+    .chain_err(|| "timed out waiting for the screen to paint")??;
+
+    let name = EntityManager::get_name_by_entity_id(entity_id)
+        .map_or_else(|| entity_id.to_string(), |name| file_name_safe(&name));
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = Path::new(SCREENSHOTS_DIR).join(format!("cef-{name}-{time}.png"));
+
+    let path = async_manager::spawn(async move {
+        fs::create_dir_all(SCREENSHOTS_DIR)?;
+        write_png(&path, &frame)?;
+        Ok::<_, Error>(path)
+    })
+    .await??;
+
+    Ok(path)
+}
+
+fn write_png(path: &Path, frame: &Frame) -> Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, frame.width, frame.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&frame.rgba)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// cef paints BGRA
+fn bgra_to_rgba(bgra: &[u8]) -> Vec<u8> {
+    bgra.chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+        .collect()
+}
+
+fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_bgra_to_rgba() {
+    assert_eq!(
+        bgra_to_rgba(&[1, 2, 3, 4, 5, 6, 7, 8]),
+        vec![3, 2, 1, 4, 7, 6, 5, 8]
+    );
+}
+
+#[test]
+fn test_file_name_safe() {
+    assert_eq!(file_name_safe("my screen"), "my_screen");
+    assert_eq!(file_name_safe("../a/b"), "___a_b");
+    assert_eq!(file_name_safe("tv-1_main"), "tv-1_main");
+}
@@ -12,9 +12,9 @@ error_chain! {
         Utf8Error(::std::str::Utf8Error);
 
         Base64(base64::DecodeError);
-        Bincode(bincode::Error);
         Clap(clap::Error);
         FuturesCanceled(futures::channel::oneshot::Canceled);
+        PngEncoding(png::EncodingError);
         Reqwest(reqwest::Error);
         SerdeJson(serde_json::Error);
         Tokio(tokio::task::JoinError);
@@ -2,6 +2,8 @@ use std::time::Duration;
 
 use classicube_sys::Vec3;
 use ncollide3d::na::Vector3;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use tracing::warn;
 
 pub fn vec3_to_vector3(v: &Vec3) -> Vector3<f32> {
     Vector3::new(v.X, v.Y, v.Z)
@@ -24,6 +26,48 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+/// the default for values we don't understand, like enum variants added in newer versions
+pub fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+
+    Ok(serde_json::from_value(value).unwrap_or_else(|e| {
+        warn!("using default for unknown value: {}", e);
+        T::default()
+    }))
+}
+
+/// stable across rust versions and machines, unlike `DefaultHasher`
+pub fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in data {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Fisher-Yates with splitmix64, so everyone shuffling with the same seed gets
+/// the same order no matter their rand version or platform
+pub fn seeded_shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    for i in (1..items.len()).rev() {
+        let j = usize::try_from(next() % (i as u64 + 1)).unwrap_or_default();
+        items.swap(i, j);
+    }
+}
+
 #[test]
 fn test_format_duration() {
     for (a, b) in &[
@@ -37,3 +81,24 @@ fn test_format_duration() {
         assert_eq!(&format_duration(*a), b);
     }
 }
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn test_seeded_shuffle() {
+    let mut a: Vec<u8> = (0..10).collect();
+    seeded_shuffle(&mut a, 1234);
+    assert_eq!(a, [0, 1, 8, 6, 4, 3, 7, 2, 9, 5]);
+
+    let mut b: Vec<u8> = (0..10).collect();
+    seeded_shuffle(&mut b, 1235);
+    assert_ne!(a, b);
+
+    let mut empty: Vec<u8> = Vec::new();
+    seeded_shuffle(&mut empty, 1234);
+}
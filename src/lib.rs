@@ -105,6 +105,7 @@ pub static mut Plugin_Component: IGameComponent = IGameComponent {
 
 test_noop_static!(Entities);
 test_noop_static!(Camera);
+test_noop_static!(Gui);
 
 test_noop_fn!(Entity_SetModel);
 test_noop_fn!(Options_Get);
@@ -115,3 +116,6 @@ test_noop_fn!(Chat_AddOf);
 test_noop_fn!(Chat_Send);
 test_noop_fn!(Gfx_CreateTexture);
 test_noop_fn!(Gfx_DeleteTexture);
+test_noop_fn!(Gui_Add);
+test_noop_fn!(Gui_Remove);
+test_noop_fn!(Convert_CP437ToUnicode);
@@ -5,15 +5,20 @@ use std::{cell::Cell, ffi::CString, os::raw::c_char};
 use classicube_sys::{cc_string, Options_Get, Options_Set, OwnedString, STRING_SIZE};
 
 use self::rust_option::RustOption;
+use crate::cef::PopupPolicy;
 
 fn get<S: Into<Vec<u8>>>(key: S) -> Option<String> {
+    get_with_capacity(key, STRING_SIZE as u16)
+}
+
+fn get_with_capacity<S: Into<Vec<u8>>>(key: S, capacity: u16) -> Option<String> {
     let c_key = CString::new(key).unwrap();
     let c_default = CString::new("").unwrap();
 
-    let mut buffer: [c_char; (STRING_SIZE as usize) + 1] = [0; (STRING_SIZE as usize) + 1];
+    let mut buffer: Vec<c_char> = vec![0; usize::from(capacity) + 1];
     let mut cc_string_value = cc_string {
         buffer: buffer.as_mut_ptr(),
-        capacity: STRING_SIZE as u16,
+        capacity,
         length: 0,
     };
 
@@ -40,6 +45,39 @@ fn set<S: Into<Vec<u8>>>(key: S, value: String) {
     }
 }
 
+const IGNORED_KEY: &str = "cef-ignored";
+/// options entries are limited to 511 bytes including the key
+const IGNORED_CAPACITY: u16 = 480;
+
+/// lowercase real names, comma separated
+pub fn get_ignored() -> Vec<String> {
+    get_with_capacity(IGNORED_KEY, IGNORED_CAPACITY)
+        .map(|value| {
+            value
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names that don't fit in `IGNORED_CAPACITY` are only ignored until restart.
+pub fn set_ignored(names: &[String]) {
+    let mut value = String::new();
+    for name in names {
+        if value.len() + name.len() + 1 > usize::from(IGNORED_CAPACITY) {
+            break;
+        }
+        if !value.is_empty() {
+            value.push(',');
+        }
+        value.push_str(name);
+    }
+
+    set(IGNORED_KEY, value);
+}
+
 macro_rules! option {
     ($name:expr, $default:expr, $type:ty) => {{
         thread_local!(
@@ -56,3 +94,10 @@ pub const VOLUME: RustOption<f32> = option!("cef-volume", 1.0, f32);
 pub const MAP_THEME_VOLUME: RustOption<f32> = option!("cef-map-theme-volume", 0.4, f32);
 pub const FRAME_RATE: RustOption<u16> = option!("cef-frame-rate", 30, u16);
 pub const SUBTITLES: RustOption<bool> = option!("cef-subtitles", true, bool);
+pub const HEARTBEAT: RustOption<bool> = option!("cef-heartbeat", false, bool);
+pub const MOUSE: RustOption<bool> = option!("cef-mouse", false, bool);
+pub const POPUPS: RustOption<PopupPolicy> = option!("cef-popups", PopupPolicy::Same, PopupPolicy);
+pub const DOWNLOADS: RustOption<bool> = option!("cef-downloads", false, bool);
+pub const DOWNLOAD_MAX_MB: RustOption<u32> = option!("cef-download-max-mb", 100, u32);
+pub const BLOCKLIST: RustOption<bool> = option!("cef-blocklist", false, bool);
+pub const REMOTE_SCREENS_MAX: RustOption<u32> = option!("cef-remote-screens-max", 3, u32);
@@ -1,6 +1,6 @@
 use tracing::warn;
 
-use super::{Player, PlayerTrait, VolumeMode, YouTubePlayer};
+use super::{Player, PlayerTrait, SoundCloudPlayer, VolumeMode, YouTubePlayer};
 use crate::{api, error::Result};
 
 #[derive(Debug, Default)]
@@ -39,12 +39,32 @@ impl PlayerBuilder {
                         }
                     }
                 }
+            } else if let Ok(player) = SoundCloudPlayer::from_input(input) {
+                if player.is_playlist {
+                    match api::soundcloud::playlist(&player.url).await {
+                        Ok(urls) => {
+                            if urls.is_empty() {
+                                // let the widget play the whole set
+                                warn!("soundcloud set gave 0 results?!");
+                            } else {
+                                ids = urls;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("couldn't fetch soundcloud set tracks: {}", e);
+                        }
+                    }
+                }
             }
         }
 
         for input in ids {
             let mut player = Player::from_input(&input)?;
 
+            if let Player::Embed(embed) = &mut player {
+                embed.resolve().await?;
+            }
+
             if let Some(autoplay) = self.autoplay {
                 player.set_autoplay(None, autoplay)?;
             }
@@ -92,8 +112,8 @@ impl PlayerBuilder {
         self
     }
 
-    /// if `use_youtube_playlist` is true, use youtube's playlist instead of
-    /// breaking up the playlist into individual players/videos
+    /// if `use_youtube_playlist` is true, use youtube's playlist (or soundcloud's set)
+    /// instead of breaking up the playlist into individual players/videos
     pub fn use_youtube_playlist(mut self, use_youtube_playlist: bool) -> Self {
         self.use_youtube_playlist = use_youtube_playlist;
         self
@@ -16,6 +16,7 @@ use crate::{
     cef::{RustRefBrowser, RustV8Value},
     chat::Chat,
     error::{bail, Result},
+    helpers::or_default,
     options,
 };
 
@@ -27,6 +28,7 @@ pub struct DashPlayer {
 
     // 0-1
     volume: f32,
+    #[serde(deserialize_with = "or_default")]
     volume_mode: VolumeMode,
 
     #[serde(skip)]
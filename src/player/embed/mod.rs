@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use classicube_helpers::{
+    async_manager,
+    color::{SILVER, TEAL},
+};
+use futures::{future::RemoteHandle, prelude::*};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::{
+    helpers::{parse_time, start_update_loop, SYNCED_MAX_LAG},
+    url_policy, PlaybackState, PlayerTrait, VolumeMode,
+};
+use crate::{
+    api,
+    cef::RustRefBrowser,
+    chat::Chat,
+    error::{bail, Result, ResultExt},
+    helpers::or_default,
+    options,
+};
+
+/// Videos from oEmbed providers (Vimeo and others) whose embeds we control
+/// with the player.js protocol.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedPlayer {
+    /// page the user linked
+    pub url: String,
+
+    /// iframe src from the oEmbed lookup, see `resolve`
+    ///
+    /// synced so others don't have to look it up again, `resolve` checks it
+    /// belongs to the same provider as `url`
+    #[serde(default)]
+    pub embed_url: Option<String>,
+    #[serde(default)]
+    pub embed_title: Option<String>,
+
+    pub time: Duration,
+
+    // 0-1
+    volume: f32,
+    #[serde(deserialize_with = "or_default")]
+    volume_mode: VolumeMode,
+
+    autoplay: bool,
+    should_loop: bool,
+    silent: bool,
+
+    #[serde(skip)]
+    pub update_loop_handle: Option<RemoteHandle<()>>,
+
+    #[serde(skip)]
+    last_title: String,
+
+    #[serde(skip)]
+    pub finished: bool,
+
+    #[serde(skip)]
+    pub create_time: Option<Instant>,
+
+    /// created from a sync message, seek precisely once playback starts
+    #[serde(skip)]
+    pub synced: bool,
+
+    #[serde(skip)]
+    pub state: PlaybackState,
+}
+
+impl Default for EmbedPlayer {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            embed_url: None,
+            embed_title: None,
+            time: Duration::from_millis(0),
+            volume: 1.0,
+            volume_mode: VolumeMode::Distance {
+                multiplier: 1.0,
+                distance: 28.0,
+            },
+            autoplay: true,
+            should_loop: false,
+            silent: false,
+            update_loop_handle: None,
+            last_title: String::new(),
+            finished: false,
+            create_time: None,
+            synced: false,
+            state: PlaybackState::Playing,
+        }
+    }
+}
+
+impl Clone for EmbedPlayer {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            embed_url: self.embed_url.clone(),
+            embed_title: self.embed_title.clone(),
+            time: self.time,
+            volume: self.volume,
+            volume_mode: self.volume_mode,
+            autoplay: self.autoplay,
+            should_loop: self.should_loop,
+            silent: self.silent,
+            ..Default::default()
+        }
+    }
+}
+
+impl PlayerTrait for EmbedPlayer {
+    fn type_name(&self) -> &'static str {
+        "Embed"
+    }
+
+    fn from_input(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            bail!("not http/https");
+        }
+
+        if api::oembed::find_endpoint(&url).is_none() {
+            bail!("not a known oEmbed provider");
+        }
+
+        // vimeo uses "#t=1m30s", others "?t=90"
+        let query: HashMap<_, _> = url.query_pairs().collect();
+        let time = query
+            .get("t")
+            .map(|t| t.to_string())
+            .or_else(|| {
+                url.fragment()
+                    .and_then(|fragment| fragment.strip_prefix("t="))
+                    .map(ToString::to_string)
+            })
+            .and_then(|t| parse_time(&t))
+            .unwrap_or_default();
+
+        Ok(Self {
+            url: url.to_string(),
+            time,
+            ..Default::default()
+        })
+    }
+
+    fn on_create(&mut self) -> Result<String> {
+        let embed_url = self
+            .embed_url
+            .as_ref()
+            .chain_err(|| "embed url wasn't resolved")?;
+        debug!("EmbedPlayer on_create {}", embed_url);
+        self.create_time = Some(Instant::now());
+
+        let mut params = vec![
+            ("url", embed_url.to_string()),
+            ("time", format!("{}", self.time.as_secs())),
+            ("volume", format!("{}", self.volume)),
+        ];
+
+        if let Some(title) = &self.embed_title {
+            params.push(("title", title.to_string()));
+        }
+
+        if self.autoplay {
+            params.push(("autoplay", "1".to_string()));
+        }
+
+        if self.should_loop {
+            params.push(("loop", "1".to_string()));
+        }
+
+        Ok(Url::parse_with_params("local://embed/", &params)?.into())
+    }
+
+    fn on_page_loaded(&mut self, entity_id: usize, _browser: &RustRefBrowser) {
+        let (f, remote_handle) = start_update_loop(entity_id).remote_handle();
+        self.update_loop_handle = Some(remote_handle);
+        async_manager::spawn_local_on_main_thread(f);
+    }
+
+    fn on_title_change(&mut self, _entity_id: usize, browser: &RustRefBrowser, title: String) {
+        if self.last_title == title || title == "Embed Loading" {
+            return;
+        }
+
+        if !self.silent {
+            Chat::print(format!("{TEAL}Now playing {SILVER}{title}"));
+        }
+
+        self.last_title = title;
+
+        if self.autoplay {
+            if let Some(create_time) = self.create_time {
+                // if it took a long time to load
+                let lag = create_time.elapsed();
+                debug!("embed started playing after loading {:?}", lag);
+                let max_lag = if self.synced {
+                    SYNCED_MAX_LAG
+                } else {
+                    Duration::from_secs(10)
+                };
+                if lag > max_lag {
+                    warn!("slow embed load, seeking to {:?}", lag);
+                    let current_time = self.time + lag;
+                    let _ignore = self.set_current_time(browser, current_time);
+                }
+            }
+        }
+    }
+
+    fn get_current_time(&self) -> Result<Duration> {
+        Ok(self.time)
+    }
+
+    fn set_current_time(&mut self, browser: &RustRefBrowser, time: Duration) -> Result<()> {
+        Self::execute(browser, &format!("setCurrentTime({})", time.as_secs_f32()))?;
+        self.time = time;
+
+        Ok(())
+    }
+
+    fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// volume is a float between 0-1
+    fn set_volume(&mut self, browser: Option<&RustRefBrowser>, volume: f32) -> Result<()> {
+        if let Some(browser) = browser {
+            if (volume - self.volume).abs() > 0.0001 {
+                let volume_modifier = options::VOLUME.get()?;
+                Self::execute(browser, &format!("setVolume({})", volume * volume_modifier))?;
+            }
+        }
+
+        self.volume = volume;
+
+        Ok(())
+    }
+
+    fn get_volume_mode(&self) -> VolumeMode {
+        self.volume_mode
+    }
+
+    fn set_volume_mode(
+        &mut self,
+        _browser: Option<&RustRefBrowser>,
+        mode: VolumeMode,
+    ) -> Result<()> {
+        if let VolumeMode::Panning { .. } = mode {
+            // the video is in a cross-origin iframe we can't reach into
+            bail!("panning not supported for embeds");
+        }
+
+        self.volume_mode = mode;
+        Ok(())
+    }
+
+    fn get_autoplay(&self) -> bool {
+        self.autoplay
+    }
+
+    fn set_autoplay(&mut self, _browser: Option<&RustRefBrowser>, autoplay: bool) -> Result<()> {
+        self.autoplay = autoplay;
+        Ok(())
+    }
+
+    fn get_loop(&self) -> bool {
+        self.should_loop
+    }
+
+    fn set_loop(&mut self, browser: Option<&RustRefBrowser>, should_loop: bool) -> Result<()> {
+        if let Some(browser) = browser {
+            Self::execute(browser, &format!("setLoop({should_loop})"))?;
+        }
+
+        self.should_loop = should_loop;
+        Ok(())
+    }
+
+    fn get_url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn get_title(&self) -> String {
+        self.last_title.clone()
+    }
+
+    fn is_finished_playing(&self) -> bool {
+        self.finished
+    }
+
+    fn set_playing(&mut self, browser: &RustRefBrowser, playing: bool) -> Result<()> {
+        Self::execute(browser, &format!("setPlaying({playing})"))?;
+        Ok(())
+    }
+
+    fn set_silent(&mut self, silent: bool) -> Result<()> {
+        self.silent = silent;
+        Ok(())
+    }
+
+    fn get_playback_state(&self) -> PlaybackState {
+        self.state
+    }
+}
+
+impl EmbedPlayer {
+    /// look up the iframe to embed, needed before `on_create`
+    ///
+    /// only checks the embed url if we already have one, from a sync or layout
+    pub async fn resolve(&mut self) -> Result<()> {
+        let url = Url::parse(&self.url)?;
+        let endpoint = api::oembed::find_endpoint(&url).chain_err(|| "no oEmbed provider")?;
+
+        if let Some(embed_url) = &self.embed_url {
+            match check_embed_url(embed_url, endpoint) {
+                Ok(()) => return Ok(()),
+
+                Err(e) => {
+                    warn!("looking up embed url again: {}", e);
+                    self.embed_url = None;
+                    self.embed_title = None;
+                }
+            }
+        }
+
+        let response = api::oembed::lookup(endpoint, &self.url).await?;
+
+        let embed_url = response
+            .iframe_src()
+            .chain_err(|| "oEmbed response had no iframe")?;
+        check_embed_url(&embed_url, endpoint)?;
+
+        self.embed_url = Some(embed_url);
+        self.embed_title = response.title;
+
+        Ok(())
+    }
+
+    fn execute(browser: &RustRefBrowser, method: &str) -> Result<()> {
+        let code = format!("window.{method};");
+        browser.execute_javascript(code)?;
+        Ok(())
+    }
+}
+
+/// the iframe must be https, from the same provider as the page and allowed
+/// by the server's url policy
+fn check_embed_url(embed_url: &str, endpoint: &str) -> Result<()> {
+    let embed_url = Url::parse(embed_url)?;
+    if embed_url.scheme() != "https" {
+        bail!("embed url {:?} isn't https", embed_url.as_str());
+    }
+    if api::oembed::find_endpoint(&embed_url) != Some(endpoint) {
+        bail!(
+            "embed url {:?} isn't from the same provider",
+            embed_url.as_str()
+        );
+    }
+    url_policy::check(embed_url.as_str())?;
+
+    Ok(())
+}
+
+#[test]
+fn test_embed() {
+    for (input, secs) in [
+        ("https://vimeo.com/76979871", 0),
+        ("https://vimeo.com/76979871#t=1m30s", 90),
+        ("https://player.vimeo.com/video/76979871", 0),
+        ("https://streamable.com/abcde?t=90", 90),
+    ] {
+        let player = EmbedPlayer::from_input(input).expect(input);
+        assert_eq!(player.time, Duration::from_secs(secs), "{}", input);
+        assert!(player.embed_url.is_none());
+    }
+
+    for input in [
+        "https://www.classicube.net/",
+        "vimeo.com/76979871",
+        "ftp://vimeo.com/76979871",
+    ] {
+        assert!(EmbedPlayer::from_input(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_check_embed_url() {
+    let vimeo = "https://vimeo.com/api/oembed.json";
+    assert!(check_embed_url("https://player.vimeo.com/video/76979871?h=abc", vimeo).is_ok());
+    assert!(check_embed_url("http://player.vimeo.com/video/76979871", vimeo).is_err());
+    assert!(check_embed_url("https://streamable.com/o/abcde", vimeo).is_err());
+    assert!(check_embed_url("https://evil.example.com/", vimeo).is_err());
+    assert!(check_embed_url("not a url", vimeo).is_err());
+}
@@ -3,16 +3,20 @@ use std::{path::Path, time::Duration};
 use classicube_helpers::async_manager;
 use classicube_sys::{Camera, Vec3};
 use ncollide3d::na::Vector3;
+use regex::Regex;
 use reqwest::Url;
-use tracing::{debug, warn};
+use tracing::warn;
 
-use super::{MediaPlayer, Player, PlayerTrait, VolumeMode, YouTubePlayer};
+use super::{PlayerTrait, VolumeMode};
 use crate::{
     entity_manager::{CefEntity, EntityManager},
-    error::{bail, Error, Result, ResultExt},
+    error::{Error, Result, ResultExt},
     helpers::vec3_to_vector3,
 };
 
+/// how far behind a synced screen can be once it starts playing before we seek
+pub const SYNCED_MAX_LAG: Duration = Duration::from_millis(250);
+
 pub async fn start_update_loop(entity_id: usize) {
     let result = start_loop(entity_id).await;
 
@@ -87,6 +91,8 @@ fn compute_real_volume(entity: &CefEntity) -> Option<(f32, VolumeMode)> {
     }
 }
 
+/// Volume follows the camera, time, state and finishing are posted by the page,
+/// see `CefEntity::on_page_message`.
 async fn start_loop(entity_id: usize) -> Result<()> {
     loop {
         // update volume
@@ -102,86 +108,8 @@ async fn start_loop(entity_id: usize) -> Result<()> {
             Ok(())
         })?;
 
-        // TODO add a has_timed
-
-        enum Kind {
-            YouTube,
-            Media,
-        }
-        let opt = EntityManager::with_entity(entity_id, |entity| {
-            // we have to do this in parts because get_real_time() is async
-            // while with_entity is not
-            Ok(match &entity.player {
-                Player::Media(_) => Some((entity.browser.clone(), Kind::Media)),
-                Player::YouTube(_) => Some((entity.browser.clone(), Kind::YouTube)),
-
-                _ => None,
-            })
-        })?;
-
-        if let Some((Some(browser), kind)) = opt {
-            // check if finished playing
-            // Do this before setting time because get_real_time will return duration
-            // instead of a current time after finishing video
-            let is_finished_playing = match kind {
-                Kind::Media => MediaPlayer::real_is_finished_playing(&browser).await?,
-                Kind::YouTube => YouTubePlayer::real_is_finished_playing(&browser).await?,
-            };
-
-            let time = match kind {
-                Kind::Media => MediaPlayer::get_real_time(&browser).await,
-                Kind::YouTube => YouTubePlayer::get_real_time(&browser).await,
-            };
-
-            // update time field for when we sync to someone else
-            if let Ok(time) = time {
-                EntityManager::with_entity(entity_id, move |entity| {
-                    match &mut entity.player {
-                        Player::Media(player) => {
-                            player.time = time;
-                        }
-                        Player::YouTube(player) => {
-                            player.time = time;
-                        }
-
-                        _ => {
-                            bail!("not supported time");
-                        }
-                    }
-                    Ok(())
-                })?;
-            }
-
-            if is_finished_playing {
-                debug!("finished playing!");
-
-                EntityManager::with_entity(entity_id, move |entity| {
-                    match &mut entity.player {
-                        Player::Media(player) => {
-                            player.finished = true;
-                            entity.skip()?;
-                        }
-
-                        Player::YouTube(player) => {
-                            player.finished = true;
-                            entity.skip()?;
-                        }
-
-                        _ => {
-                            bail!("is_finished_playing not supported");
-                        }
-                    }
-                    Ok(())
-                })?;
-
-                break;
-            }
-        }
-
         async_manager::sleep(Duration::from_millis(32)).await;
     }
-
-    Ok(())
 }
 
 pub fn get_ext(url: &Url) -> Result<&str> {
@@ -205,6 +133,26 @@ pub fn get_ext(url: &Url) -> Result<&str> {
         })
 }
 
+/// "1h2m3s", "90s" or "90"
+pub fn parse_time(input: &str) -> Option<Duration> {
+    if let Ok(secs) = input.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let captures = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$")
+        .unwrap()
+        .captures(input)?;
+
+    let mut secs = 0;
+    for (i, multiplier) in [(1, 60 * 60), (2, 60), (3, 1)] {
+        if let Some(n) = captures.get(i) {
+            secs += n.as_str().parse::<u64>().ok()? * multiplier;
+        }
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
 #[test]
 fn test_get_ext() {
     assert_eq!(
@@ -220,3 +168,13 @@ fn test_get_ext() {
         "ogg"
     );
 }
+
+#[test]
+fn test_parse_time() {
+    assert_eq!(parse_time("90"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_time("90s"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_time("1m30s"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_time("1h2m3s"), Some(Duration::from_secs(3723)));
+    assert_eq!(parse_time("1h"), Some(Duration::from_secs(3600)));
+    assert_eq!(parse_time("abc"), None);
+}
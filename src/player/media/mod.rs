@@ -10,15 +10,10 @@ use tracing::{debug, warn};
 use url::Url;
 
 use super::{
-    helpers::{get_ext, start_update_loop},
-    PlayerTrait, VolumeMode, WebPlayer,
-};
-use crate::{
-    cef::{RustRefBrowser, RustV8Value},
-    chat::Chat,
-    error::{bail, Result},
-    options,
+    helpers::{get_ext, start_update_loop, SYNCED_MAX_LAG},
+    PlaybackState, PlayerTrait, VolumeMode, WebPlayer,
 };
+use crate::{cef::RustRefBrowser, chat::Chat, error::Result, helpers::or_default, options};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MediaPlayer {
@@ -27,12 +22,13 @@ pub struct MediaPlayer {
 
     // 0-1
     volume: f32,
+    #[serde(deserialize_with = "or_default")]
     volume_mode: VolumeMode,
 
     autoplay: bool,
     should_loop: bool,
     silent: bool,
-    speed: f32,
+    pub speed: f32,
 
     #[serde(skip)]
     pub update_loop_handle: Option<RemoteHandle<()>>,
@@ -45,6 +41,12 @@ pub struct MediaPlayer {
 
     #[serde(skip)]
     pub create_time: Option<Instant>,
+
+    /// created from a sync message, seek precisely once playback starts
+    #[serde(skip)]
+    pub synced: bool,
+    #[serde(skip)]
+    pub state: PlaybackState,
 }
 
 impl Default for MediaPlayer {
@@ -65,6 +67,8 @@ impl Default for MediaPlayer {
             last_title: String::new(),
             finished: false,
             create_time: None,
+            synced: false,
+            state: PlaybackState::Playing,
         }
     }
 }
@@ -181,7 +185,12 @@ impl PlayerTrait for MediaPlayer {
                 let lag = now - create_time;
                 debug!("media started playing after loading {:?}", lag);
                 // TODO delay everyone a couple seconds then start playing video!
-                if lag > Duration::from_secs(10) {
+                let max_lag = if self.synced {
+                    SYNCED_MAX_LAG
+                } else {
+                    Duration::from_secs(10)
+                };
+                if lag > max_lag {
                     // TODO don't do this if longer than video duration
                     warn!("slow media load, seeking to {:?}", lag);
                     // seek to current time
@@ -288,6 +297,10 @@ impl PlayerTrait for MediaPlayer {
         Ok(())
     }
 
+    fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
     fn set_speed(&mut self, browser: Option<&RustRefBrowser>, speed: f32) -> Result<()> {
         if let Some(browser) = browser {
             Self::execute(browser, &format!("setPlaybackRate({speed})"))?;
@@ -296,42 +309,21 @@ impl PlayerTrait for MediaPlayer {
         self.speed = speed;
         Ok(())
     }
-}
 
-impl MediaPlayer {
-    pub async fn real_is_finished_playing(browser: &RustRefBrowser) -> Result<bool> {
-        let ended = match Self::eval(browser, "playerFinished").await? {
-            RustV8Value::Bool(ended) => ended,
-
-            other => {
-                bail!("non-bool js value {:?}", other);
-            }
-        };
-
-        Ok(ended)
+    fn set_playback_rate(&self, browser: &RustRefBrowser, rate: f32) -> Result<()> {
+        Self::execute(browser, &format!("setPlaybackRate({rate})"))?;
+        Ok(())
     }
 
-    pub async fn get_real_time(browser: &RustRefBrowser) -> Result<Duration> {
-        let seconds = match Self::eval(browser, "getCurrentTime()").await? {
-            RustV8Value::Double(seconds) => seconds as f32,
-            RustV8Value::Int(seconds) => seconds as f32,
-            RustV8Value::UInt(seconds) => seconds as f32,
-            _ => {
-                bail!("non-number js value");
-            }
-        };
-
-        Ok(Duration::from_secs_f32(seconds))
+    fn get_playback_state(&self) -> PlaybackState {
+        self.state
     }
+}
 
+impl MediaPlayer {
     fn execute(browser: &RustRefBrowser, method: &str) -> Result<()> {
         let code = format!("window.{method};");
         browser.execute_javascript(code)?;
         Ok(())
     }
-
-    async fn eval(browser: &RustRefBrowser, method: &str) -> Result<RustV8Value> {
-        let code = format!("window.{method};");
-        browser.eval_javascript(code).await
-    }
 }
@@ -1,10 +1,14 @@
 mod builder;
 mod dash;
+mod embed;
 mod helpers;
 mod hls;
 mod image;
 mod media;
+mod soundcloud;
+mod twitch;
 pub mod url_aliases;
+pub mod url_policy;
 mod volume_fade;
 mod web;
 mod youtube;
@@ -14,8 +18,9 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 
 pub use self::{
-    builder::PlayerBuilder, dash::DashPlayer, hls::HlsPlayer, image::ImagePlayer,
-    media::MediaPlayer, web::WebPlayer, youtube::YouTubePlayer,
+    builder::PlayerBuilder, dash::DashPlayer, embed::EmbedPlayer, hls::HlsPlayer,
+    image::ImagePlayer, media::MediaPlayer, twitch::TwitchPlayer, web::WebPlayer,
+    youtube::YouTubePlayer,
 };
 use crate::{
     cef::RustRefBrowser,
@@ -106,6 +111,9 @@ pub trait PlayerTrait: Clone {
         }
     }
 
+    fn get_speed(&self) -> f32 {
+        1.0
+    }
     fn set_speed(&mut self, _browser: Option<&RustRefBrowser>, speed: f32) -> Result<()> {
         if (speed - 1.0).abs() > 0.01 {
             bail!("setting speed unsupported");
@@ -113,6 +121,37 @@ pub trait PlayerTrait: Clone {
             Ok(())
         }
     }
+
+    /// Plays at `rate` without changing the speed we send to others,
+    /// used while catching up to a sync source.
+    fn set_playback_rate(&self, _browser: &RustRefBrowser, _rate: f32) -> Result<()> {
+        bail!("setting playback rate unsupported");
+    }
+
+    fn get_playback_state(&self) -> PlaybackState {
+        PlaybackState::Playing
+    }
+}
+
+/// what the page was last seen doing
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub enum PlaybackState {
+    #[default]
+    Playing,
+    Paused,
+    Ended,
+}
+
+impl PlaybackState {
+    /// from the pages' `getPlaybackState()`
+    pub fn from_js(state: &str) -> Option<Self> {
+        match state {
+            "playing" => Some(Self::Playing),
+            "paused" => Some(Self::Paused),
+            "ended" => Some(Self::Ended),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
@@ -129,9 +168,22 @@ pub enum VolumeMode {
     },
 }
 
+/// what new players start with
+impl Default for VolumeMode {
+    fn default() -> Self {
+        Self::Distance {
+            multiplier: 1.0,
+            distance: 28.0,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Player {
     YouTube(YouTubePlayer),
+    Twitch(TwitchPlayer),
+    Embed(EmbedPlayer),
+    SoundCloud(SoundCloudPlayer),
     Dash(DashPlayer),
     Hls(HlsPlayer),
     Media(MediaPlayer),
@@ -143,6 +195,9 @@ impl PlayerTrait for Player {
     fn type_name(&self) -> &'static str {
         match self {
             Player::YouTube(player) => player.type_name(),
+            Player::Twitch(player) => player.type_name(),
+            Player::Embed(player) => player.type_name(),
+            Player::SoundCloud(player) => player.type_name(),
             Player::Dash(player) => player.type_name(),
             Player::Hls(player) => player.type_name(),
             Player::Media(player) => player.type_name(),
@@ -152,54 +207,20 @@ impl PlayerTrait for Player {
     }
 
     fn from_input(input: &str) -> Result<Self> {
-        if let Ok(player) = YouTubePlayer::from_input(input) {
-            return Ok(Player::YouTube(player));
-        }
-
-        if let Ok(player) = DashPlayer::from_input(input) {
-            return Ok(Player::Dash(player));
-        }
-
-        if let Ok(player) = HlsPlayer::from_input(input) {
-            return Ok(Player::Hls(player));
-        }
-
-        if let Ok(player) = MediaPlayer::from_input(input) {
-            return Ok(Player::Media(player));
-        }
-
-        if let Ok(player) = ImagePlayer::from_input(input) {
-            return Ok(Player::Image(player));
-        }
-
-        match WebPlayer::from_input(input) {
-            Ok(player) => Ok(Player::Web(player)),
-
-            Err(e) => {
-                if input.starts_with("https://") || input.starts_with("http://") {
-                    return Err(e);
-                }
-
-                // try again with https:// in front
-                match Player::from_input(&format!("https://{input}")) {
-                    Ok(player) => Ok(player),
-                    Err(player_err) => {
-                        // try resolving alias
-                        match url_aliases::resolve_alias_url(input) {
-                            Ok(url) => Player::from_input(&url),
-                            Err(alias_err) => {
-                                bail!("{} (and when resolving alias: {})", player_err, alias_err);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let player = Self::from_input_any(input)?;
+        url_policy::check(&player.get_url())?;
+        Ok(player)
     }
 
     fn on_create(&mut self) -> Result<String> {
+        // also catches screens synced from other players
+        url_policy::check(&self.get_url())?;
+
         match self {
             Player::YouTube(player) => player.on_create(),
+            Player::Twitch(player) => player.on_create(),
+            Player::Embed(player) => player.on_create(),
+            Player::SoundCloud(player) => player.on_create(),
             Player::Dash(player) => player.on_create(),
             Player::Hls(player) => player.on_create(),
             Player::Media(player) => player.on_create(),
@@ -211,6 +232,9 @@ impl PlayerTrait for Player {
     fn on_page_loaded(&mut self, entity_id: usize, browser: &RustRefBrowser) {
         match self {
             Player::YouTube(player) => player.on_page_loaded(entity_id, browser),
+            Player::Twitch(player) => player.on_page_loaded(entity_id, browser),
+            Player::Embed(player) => player.on_page_loaded(entity_id, browser),
+            Player::SoundCloud(player) => player.on_page_loaded(entity_id, browser),
             Player::Dash(player) => player.on_page_loaded(entity_id, browser),
             Player::Hls(player) => player.on_page_loaded(entity_id, browser),
             Player::Media(player) => player.on_page_loaded(entity_id, browser),
@@ -222,6 +246,9 @@ impl PlayerTrait for Player {
     fn on_title_change(&mut self, entity_id: usize, browser: &RustRefBrowser, title: String) {
         match self {
             Player::YouTube(player) => player.on_title_change(entity_id, browser, title),
+            Player::Twitch(player) => player.on_title_change(entity_id, browser, title),
+            Player::Embed(player) => player.on_title_change(entity_id, browser, title),
+            Player::SoundCloud(player) => player.on_title_change(entity_id, browser, title),
             Player::Dash(player) => player.on_title_change(entity_id, browser, title),
             Player::Hls(player) => player.on_title_change(entity_id, browser, title),
             Player::Media(player) => player.on_title_change(entity_id, browser, title),
@@ -233,6 +260,9 @@ impl PlayerTrait for Player {
     fn get_current_time(&self) -> Result<Duration> {
         match self {
             Player::YouTube(player) => player.get_current_time(),
+            Player::Twitch(player) => player.get_current_time(),
+            Player::Embed(player) => player.get_current_time(),
+            Player::SoundCloud(player) => player.get_current_time(),
             Player::Dash(player) => player.get_current_time(),
             Player::Hls(player) => player.get_current_time(),
             Player::Media(player) => player.get_current_time(),
@@ -244,6 +274,9 @@ impl PlayerTrait for Player {
     fn set_current_time(&mut self, browser: &RustRefBrowser, time: Duration) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_current_time(browser, time),
+            Player::Twitch(player) => player.set_current_time(browser, time),
+            Player::Embed(player) => player.set_current_time(browser, time),
+            Player::SoundCloud(player) => player.set_current_time(browser, time),
             Player::Dash(player) => player.set_current_time(browser, time),
             Player::Hls(player) => player.set_current_time(browser, time),
             Player::Media(player) => player.set_current_time(browser, time),
@@ -255,6 +288,9 @@ impl PlayerTrait for Player {
     fn get_volume(&self) -> f32 {
         match self {
             Player::YouTube(player) => player.get_volume(),
+            Player::Twitch(player) => player.get_volume(),
+            Player::Embed(player) => player.get_volume(),
+            Player::SoundCloud(player) => player.get_volume(),
             Player::Dash(player) => player.get_volume(),
             Player::Hls(player) => player.get_volume(),
             Player::Media(player) => player.get_volume(),
@@ -266,6 +302,9 @@ impl PlayerTrait for Player {
     fn set_volume(&mut self, browser: Option<&RustRefBrowser>, percent: f32) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_volume(browser, percent),
+            Player::Twitch(player) => player.set_volume(browser, percent),
+            Player::Embed(player) => player.set_volume(browser, percent),
+            Player::SoundCloud(player) => player.set_volume(browser, percent),
             Player::Dash(player) => player.set_volume(browser, percent),
             Player::Hls(player) => player.set_volume(browser, percent),
             Player::Media(player) => player.set_volume(browser, percent),
@@ -277,6 +316,9 @@ impl PlayerTrait for Player {
     fn get_volume_mode(&self) -> VolumeMode {
         match self {
             Player::YouTube(player) => player.get_volume_mode(),
+            Player::Twitch(player) => player.get_volume_mode(),
+            Player::Embed(player) => player.get_volume_mode(),
+            Player::SoundCloud(player) => player.get_volume_mode(),
             Player::Dash(player) => player.get_volume_mode(),
             Player::Hls(player) => player.get_volume_mode(),
             Player::Media(player) => player.get_volume_mode(),
@@ -292,6 +334,9 @@ impl PlayerTrait for Player {
     ) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_volume_mode(browser, mode),
+            Player::Twitch(player) => player.set_volume_mode(browser, mode),
+            Player::Embed(player) => player.set_volume_mode(browser, mode),
+            Player::SoundCloud(player) => player.set_volume_mode(browser, mode),
             Player::Dash(player) => player.set_volume_mode(browser, mode),
             Player::Hls(player) => player.set_volume_mode(browser, mode),
             Player::Media(player) => player.set_volume_mode(browser, mode),
@@ -303,6 +348,9 @@ impl PlayerTrait for Player {
     fn get_autoplay(&self) -> bool {
         match self {
             Player::YouTube(player) => player.get_autoplay(),
+            Player::Twitch(player) => player.get_autoplay(),
+            Player::Embed(player) => player.get_autoplay(),
+            Player::SoundCloud(player) => player.get_autoplay(),
             Player::Dash(player) => player.get_autoplay(),
             Player::Hls(player) => player.get_autoplay(),
             Player::Media(player) => player.get_autoplay(),
@@ -314,6 +362,9 @@ impl PlayerTrait for Player {
     fn set_autoplay(&mut self, browser: Option<&RustRefBrowser>, autoplay: bool) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_autoplay(browser, autoplay),
+            Player::Twitch(player) => player.set_autoplay(browser, autoplay),
+            Player::Embed(player) => player.set_autoplay(browser, autoplay),
+            Player::SoundCloud(player) => player.set_autoplay(browser, autoplay),
             Player::Dash(player) => player.set_autoplay(browser, autoplay),
             Player::Hls(player) => player.set_autoplay(browser, autoplay),
             Player::Media(player) => player.set_autoplay(browser, autoplay),
@@ -325,6 +376,9 @@ impl PlayerTrait for Player {
     fn get_loop(&self) -> bool {
         match self {
             Player::YouTube(player) => player.get_loop(),
+            Player::Twitch(player) => player.get_loop(),
+            Player::Embed(player) => player.get_loop(),
+            Player::SoundCloud(player) => player.get_loop(),
             Player::Dash(player) => player.get_loop(),
             Player::Hls(player) => player.get_loop(),
             Player::Media(player) => player.get_loop(),
@@ -336,6 +390,9 @@ impl PlayerTrait for Player {
     fn set_loop(&mut self, browser: Option<&RustRefBrowser>, should_loop: bool) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_loop(browser, should_loop),
+            Player::Twitch(player) => player.set_loop(browser, should_loop),
+            Player::Embed(player) => player.set_loop(browser, should_loop),
+            Player::SoundCloud(player) => player.set_loop(browser, should_loop),
             Player::Dash(player) => player.set_loop(browser, should_loop),
             Player::Hls(player) => player.set_loop(browser, should_loop),
             Player::Media(player) => player.set_loop(browser, should_loop),
@@ -347,6 +404,9 @@ impl PlayerTrait for Player {
     fn get_url(&self) -> String {
         match self {
             Player::YouTube(player) => player.get_url(),
+            Player::Twitch(player) => player.get_url(),
+            Player::Embed(player) => player.get_url(),
+            Player::SoundCloud(player) => player.get_url(),
             Player::Dash(player) => player.get_url(),
             Player::Hls(player) => player.get_url(),
             Player::Media(player) => player.get_url(),
@@ -358,6 +418,9 @@ impl PlayerTrait for Player {
     fn get_title(&self) -> String {
         match self {
             Player::YouTube(player) => player.get_title(),
+            Player::Twitch(player) => player.get_title(),
+            Player::Embed(player) => player.get_title(),
+            Player::SoundCloud(player) => player.get_title(),
             Player::Dash(player) => player.get_title(),
             Player::Hls(player) => player.get_title(),
             Player::Media(player) => player.get_title(),
@@ -369,6 +432,9 @@ impl PlayerTrait for Player {
     fn is_finished_playing(&self) -> bool {
         match self {
             Player::YouTube(player) => player.is_finished_playing(),
+            Player::Twitch(player) => player.is_finished_playing(),
+            Player::Embed(player) => player.is_finished_playing(),
+            Player::SoundCloud(player) => player.is_finished_playing(),
             Player::Dash(player) => player.is_finished_playing(),
             Player::Hls(player) => player.is_finished_playing(),
             Player::Media(player) => player.is_finished_playing(),
@@ -380,6 +446,9 @@ impl PlayerTrait for Player {
     fn set_playing(&mut self, browser: &RustRefBrowser, playing: bool) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_playing(browser, playing),
+            Player::Twitch(player) => player.set_playing(browser, playing),
+            Player::Embed(player) => player.set_playing(browser, playing),
+            Player::SoundCloud(player) => player.set_playing(browser, playing),
             Player::Dash(player) => player.set_playing(browser, playing),
             Player::Hls(player) => player.set_playing(browser, playing),
             Player::Media(player) => player.set_playing(browser, playing),
@@ -391,6 +460,9 @@ impl PlayerTrait for Player {
     fn set_silent(&mut self, silent: bool) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_silent(silent),
+            Player::Twitch(player) => player.set_silent(silent),
+            Player::Embed(player) => player.set_silent(silent),
+            Player::SoundCloud(player) => player.set_silent(silent),
             Player::Dash(player) => player.set_silent(silent),
             Player::Hls(player) => player.set_silent(silent),
             Player::Media(player) => player.set_silent(silent),
@@ -399,9 +471,26 @@ impl PlayerTrait for Player {
         }
     }
 
+    fn get_speed(&self) -> f32 {
+        match self {
+            Player::YouTube(player) => player.get_speed(),
+            Player::Twitch(player) => player.get_speed(),
+            Player::Embed(player) => player.get_speed(),
+            Player::SoundCloud(player) => player.get_speed(),
+            Player::Dash(player) => player.get_speed(),
+            Player::Hls(player) => player.get_speed(),
+            Player::Media(player) => player.get_speed(),
+            Player::Image(player) => player.get_speed(),
+            Player::Web(player) => player.get_speed(),
+        }
+    }
+
     fn set_speed(&mut self, browser: Option<&RustRefBrowser>, speed: f32) -> Result<()> {
         match self {
             Player::YouTube(player) => player.set_speed(browser, speed),
+            Player::Twitch(player) => player.set_speed(browser, speed),
+            Player::Embed(player) => player.set_speed(browser, speed),
+            Player::SoundCloud(player) => player.set_speed(browser, speed),
             Player::Dash(player) => player.set_speed(browser, speed),
             Player::Hls(player) => player.set_speed(browser, speed),
             Player::Media(player) => player.set_speed(browser, speed),
@@ -409,6 +498,95 @@ impl PlayerTrait for Player {
             Player::Web(player) => player.set_speed(browser, speed),
         }
     }
+
+    fn set_playback_rate(&self, browser: &RustRefBrowser, rate: f32) -> Result<()> {
+        match self {
+            Player::YouTube(player) => player.set_playback_rate(browser, rate),
+            Player::Twitch(player) => player.set_playback_rate(browser, rate),
+            Player::Embed(player) => player.set_playback_rate(browser, rate),
+            Player::SoundCloud(player) => player.set_playback_rate(browser, rate),
+            Player::Dash(player) => player.set_playback_rate(browser, rate),
+            Player::Hls(player) => player.set_playback_rate(browser, rate),
+            Player::Media(player) => player.set_playback_rate(browser, rate),
+            Player::Image(player) => player.set_playback_rate(browser, rate),
+            Player::Web(player) => player.set_playback_rate(browser, rate),
+        }
+    }
+
+    fn get_playback_state(&self) -> PlaybackState {
+        match self {
+            Player::YouTube(player) => player.get_playback_state(),
+            Player::Twitch(player) => player.get_playback_state(),
+            Player::Embed(player) => player.get_playback_state(),
+            Player::SoundCloud(player) => player.get_playback_state(),
+            Player::Dash(player) => player.get_playback_state(),
+            Player::Hls(player) => player.get_playback_state(),
+            Player::Media(player) => player.get_playback_state(),
+            Player::Image(player) => player.get_playback_state(),
+            Player::Web(player) => player.get_playback_state(),
+        }
+    }
+}
+
+impl Player {
+    /// `from_input` without the server's url policy
+    fn from_input_any(input: &str) -> Result<Self> {
+        if let Ok(player) = YouTubePlayer::from_input(input) {
+            return Ok(Player::YouTube(player));
+        }
+
+        if let Ok(player) = TwitchPlayer::from_input(input) {
+            return Ok(Player::Twitch(player));
+        }
+
+        if let Ok(player) = EmbedPlayer::from_input(input) {
+            return Ok(Player::Embed(player));
+        }
+
+        if let Ok(player) = SoundCloudPlayer::from_input(input) {
+            return Ok(Player::SoundCloud(player));
+        }
+
+        if let Ok(player) = DashPlayer::from_input(input) {
+            return Ok(Player::Dash(player));
+        }
+
+        if let Ok(player) = HlsPlayer::from_input(input) {
+            return Ok(Player::Hls(player));
+        }
+
+        if let Ok(player) = MediaPlayer::from_input(input) {
+            return Ok(Player::Media(player));
+        }
+
+        if let Ok(player) = ImagePlayer::from_input(input) {
+            return Ok(Player::Image(player));
+        }
+
+        match WebPlayer::from_input(input) {
+            Ok(player) => Ok(Player::Web(player)),
+
+            Err(e) => {
+                if input.starts_with("https://") || input.starts_with("http://") {
+                    return Err(e);
+                }
+
+                // try again with https:// in front
+                match Player::from_input(&format!("https://{input}")) {
+                    Ok(player) => Ok(player),
+                    Err(player_err) => {
+                        // try resolving alias
+                        match url_aliases::resolve_alias_url(input) {
+                            Ok(url) => Player::from_input(&url),
+                            Err(alias_err) => {
+                                bail!("{} (and when resolving alias: {})", player_err, alias_err);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub fn on_new_map() {
@@ -472,3 +650,17 @@ fn test_create_player() {
         }
     }
 }
+
+#[test]
+fn test_playback_state_from_js() {
+    assert_eq!(
+        PlaybackState::from_js("playing"),
+        Some(PlaybackState::Playing)
+    );
+    assert_eq!(
+        PlaybackState::from_js("paused"),
+        Some(PlaybackState::Paused)
+    );
+    assert_eq!(PlaybackState::from_js("ended"), Some(PlaybackState::Ended));
+    assert_eq!(PlaybackState::from_js("buffering"), None);
+}
@@ -0,0 +1,442 @@
+use std::time::{Duration, Instant};
+
+use classicube_helpers::{
+    async_manager,
+    color::{SILVER, TEAL},
+};
+use futures::{future::RemoteHandle, prelude::*};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::{
+    helpers::{parse_time, start_update_loop, SYNCED_MAX_LAG},
+    PlaybackState, PlayerTrait, VolumeMode,
+};
+use crate::{
+    cef::RustRefBrowser,
+    chat::Chat,
+    error::{bail, Result, ResultExt},
+    helpers::or_default,
+    options,
+};
+
+/// first path segments on soundcloud.com that aren't users
+const RESERVED_PATHS: &[&str] = &[
+    "charts", "discover", "feed", "mobile", "pages", "search", "settings", "stream", "upload",
+    "you",
+];
+
+/// second path segments on soundcloud.com that are user pages, not tracks
+const USER_PAGES: &[&str] = &[
+    "albums",
+    "comments",
+    "followers",
+    "following",
+    "likes",
+    "popular-tracks",
+    "reposts",
+    "sets",
+    "tracks",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SoundCloudPlayer {
+    pub url: String,
+    pub time: Duration,
+
+    /// a set, played as one item by the widget
+    pub is_playlist: bool,
+
+    // 0-1
+    volume: f32,
+    #[serde(deserialize_with = "or_default")]
+    volume_mode: VolumeMode,
+
+    autoplay: bool,
+    should_loop: bool,
+    silent: bool,
+
+    #[serde(skip)]
+    pub update_loop_handle: Option<RemoteHandle<()>>,
+
+    #[serde(skip)]
+    last_title: String,
+
+    #[serde(skip)]
+    pub finished: bool,
+
+    #[serde(skip)]
+    pub create_time: Option<Instant>,
+
+    /// created from a sync message, seek precisely once playback starts
+    #[serde(skip)]
+    pub synced: bool,
+
+    #[serde(skip)]
+    pub state: PlaybackState,
+}
+
+impl Default for SoundCloudPlayer {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            time: Duration::from_millis(0),
+            is_playlist: false,
+            volume: 1.0,
+            volume_mode: VolumeMode::Distance {
+                multiplier: 1.0,
+                distance: 28.0,
+            },
+            autoplay: true,
+            should_loop: false,
+            silent: false,
+            update_loop_handle: None,
+            last_title: String::new(),
+            finished: false,
+            create_time: None,
+            synced: false,
+            state: PlaybackState::Playing,
+        }
+    }
+}
+
+impl Clone for SoundCloudPlayer {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            time: self.time,
+            is_playlist: self.is_playlist,
+            volume: self.volume,
+            volume_mode: self.volume_mode,
+            autoplay: self.autoplay,
+            should_loop: self.should_loop,
+            silent: self.silent,
+            ..Default::default()
+        }
+    }
+}
+
+impl PlayerTrait for SoundCloudPlayer {
+    fn type_name(&self) -> &'static str {
+        "SoundCloud"
+    }
+
+    fn from_input(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            bail!("not http/https");
+        }
+
+        let host_str = url.host_str().chain_err(|| "no host")?;
+        let segments: Vec<_> = url
+            .path_segments()
+            .chain_err(|| "no path segments")?
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let is_playlist = match (host_str, segments.as_slice()) {
+            // share links, the widget resolves these
+            ("on.soundcloud.com", [_]) => false,
+
+            ("soundcloud.com" | "www.soundcloud.com" | "m.soundcloud.com", segments) => {
+                match segments {
+                    [user, rest @ ..] if RESERVED_PATHS.contains(user) || rest.is_empty() => {
+                        bail!("not a soundcloud track or set");
+                    }
+
+                    [_, "sets", _] | [_, "sets", _, _] => true,
+
+                    [_, track] | [_, track, _] if !USER_PAGES.contains(track) => {
+                        // 3rd segment is the secret token of private tracks
+                        if segments.len() == 3 && !segments[2].starts_with("s-") {
+                            bail!("not a soundcloud track");
+                        }
+
+                        false
+                    }
+
+                    _ => bail!("not a soundcloud track or set"),
+                }
+            }
+
+            _ => bail!("not a soundcloud url"),
+        };
+
+        // "#t=1:30" or "#t=90"
+        let time = url
+            .fragment()
+            .and_then(|fragment| fragment.strip_prefix("t="))
+            .and_then(|t| match t.split_once(':') {
+                Some((minutes, seconds)) => {
+                    let minutes: u64 = minutes.parse().ok()?;
+                    let seconds: u64 = seconds.parse().ok()?;
+                    Some(Duration::from_secs(minutes * 60 + seconds))
+                }
+                None => parse_time(t),
+            })
+            .unwrap_or_default();
+
+        let mut url = url;
+        url.set_query(None);
+        url.set_fragment(None);
+
+        Ok(Self {
+            url: url.to_string(),
+            time,
+            is_playlist,
+            ..Default::default()
+        })
+    }
+
+    fn on_create(&mut self) -> Result<String> {
+        debug!("SoundCloudPlayer on_create {}", self.url);
+        self.create_time = Some(Instant::now());
+
+        let mut params = vec![
+            ("url", self.url.to_string()),
+            ("time", format!("{}", self.time.as_secs())),
+            ("volume", format!("{}", self.volume)),
+        ];
+
+        if self.autoplay {
+            params.push(("autoplay", "1".to_string()));
+        }
+
+        if self.should_loop {
+            params.push(("loop", "1".to_string()));
+        }
+
+        Ok(Url::parse_with_params("local://soundcloud/", &params)?.into())
+    }
+
+    fn on_page_loaded(&mut self, entity_id: usize, _browser: &RustRefBrowser) {
+        let (f, remote_handle) = start_update_loop(entity_id).remote_handle();
+        self.update_loop_handle = Some(remote_handle);
+        async_manager::spawn_local_on_main_thread(f);
+    }
+
+    fn on_title_change(&mut self, _entity_id: usize, browser: &RustRefBrowser, title: String) {
+        if self.last_title == title || title == "SoundCloud Loading" {
+            return;
+        }
+
+        if !self.silent {
+            Chat::print(format!("{TEAL}Now playing {SILVER}{title}"));
+        }
+
+        self.last_title = title;
+
+        // sets will show multiple titles
+        if self.autoplay && !self.is_playlist {
+            if let Some(create_time) = self.create_time {
+                // if it took a long time to load
+                let lag = create_time.elapsed();
+                debug!("soundcloud started playing after loading {:?}", lag);
+                let max_lag = if self.synced {
+                    SYNCED_MAX_LAG
+                } else {
+                    Duration::from_secs(10)
+                };
+                if lag > max_lag {
+                    warn!("slow soundcloud load, seeking to {:?}", lag);
+                    let current_time = self.time + lag;
+                    let _ignore = self.set_current_time(browser, current_time);
+                }
+            }
+        }
+    }
+
+    fn get_current_time(&self) -> Result<Duration> {
+        Ok(self.time)
+    }
+
+    fn set_current_time(&mut self, browser: &RustRefBrowser, time: Duration) -> Result<()> {
+        Self::execute(browser, &format!("setCurrentTime({})", time.as_secs_f32()))?;
+        self.time = time;
+
+        Ok(())
+    }
+
+    fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// volume is a float between 0-1
+    fn set_volume(&mut self, browser: Option<&RustRefBrowser>, volume: f32) -> Result<()> {
+        if let Some(browser) = browser {
+            if (volume - self.volume).abs() > 0.0001 {
+                let volume_modifier = options::VOLUME.get()?;
+                Self::execute(browser, &format!("setVolume({})", volume * volume_modifier))?;
+            }
+        }
+
+        self.volume = volume;
+
+        Ok(())
+    }
+
+    fn get_volume_mode(&self) -> VolumeMode {
+        self.volume_mode
+    }
+
+    fn set_volume_mode(
+        &mut self,
+        browser: Option<&RustRefBrowser>,
+        mode: VolumeMode,
+    ) -> Result<()> {
+        if let Some(browser) = browser {
+            if let VolumeMode::Panning { pan, .. } = mode {
+                // TODO less big string!
+                let _ignore = browser.execute_javascript_on_frame(
+                    "https://w.soundcloud.com",
+                    format!(
+                        r#"
+                            if (typeof window.panner === "undefined") {{
+                                var audio = document.getElementsByTagName("audio")[0];
+                                var context = new AudioContext();
+                                var source = context.createMediaElementSource(audio);
+                                var panner = context.createStereoPanner();
+                                source.connect(panner);
+                                panner.connect(context.destination);
+                                window.panner = panner;
+                                window.context = context;
+                            }}
+                            window.panner.pan.setTargetAtTime(
+                                {pan},
+                                window.context.currentTime,
+                                0.02
+                            );
+                        "#
+                    ),
+                );
+            } else {
+                let _ignore = browser.execute_javascript_on_frame(
+                    "https://w.soundcloud.com",
+                    r#"
+                        if (typeof window.panner !== "undefined") {
+                            window.panner.pan.value = 0.0;
+                        }
+                    "#,
+                );
+            }
+        }
+
+        self.volume_mode = mode;
+        Ok(())
+    }
+
+    fn get_autoplay(&self) -> bool {
+        self.autoplay
+    }
+
+    fn set_autoplay(&mut self, _browser: Option<&RustRefBrowser>, autoplay: bool) -> Result<()> {
+        self.autoplay = autoplay;
+        Ok(())
+    }
+
+    fn get_loop(&self) -> bool {
+        self.should_loop
+    }
+
+    fn set_loop(&mut self, browser: Option<&RustRefBrowser>, should_loop: bool) -> Result<()> {
+        if let Some(browser) = browser {
+            Self::execute(browser, &format!("setLoop({should_loop})"))?;
+        }
+
+        self.should_loop = should_loop;
+        Ok(())
+    }
+
+    fn get_url(&self) -> String {
+        let secs = self.time.as_secs();
+        if secs == 0 {
+            self.url.clone()
+        } else {
+            format!("{}#t={}:{:02}", self.url, secs / 60, secs % 60)
+        }
+    }
+
+    fn get_title(&self) -> String {
+        self.last_title.clone()
+    }
+
+    fn is_finished_playing(&self) -> bool {
+        self.finished
+    }
+
+    fn set_playing(&mut self, browser: &RustRefBrowser, playing: bool) -> Result<()> {
+        Self::execute(browser, &format!("setPlaying({playing})"))?;
+        Ok(())
+    }
+
+    fn set_silent(&mut self, silent: bool) -> Result<()> {
+        self.silent = silent;
+        Ok(())
+    }
+
+    fn get_playback_state(&self) -> PlaybackState {
+        self.state
+    }
+}
+
+impl SoundCloudPlayer {
+    fn execute(browser: &RustRefBrowser, method: &str) -> Result<()> {
+        let code = format!("window.{method};");
+        browser.execute_javascript(code)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_soundcloud() {
+    for (input, url, is_playlist, secs) in [
+        (
+            "https://soundcloud.com/artist/some-track",
+            "https://soundcloud.com/artist/some-track",
+            false,
+            0,
+        ),
+        (
+            "https://soundcloud.com/artist/some-track?si=abc&utm_source=clipboard#t=1:30",
+            "https://soundcloud.com/artist/some-track",
+            false,
+            90,
+        ),
+        (
+            "https://m.soundcloud.com/artist/some-track/s-AbCdE",
+            "https://m.soundcloud.com/artist/some-track/s-AbCdE",
+            false,
+            0,
+        ),
+        (
+            "https://soundcloud.com/artist/sets/some-set",
+            "https://soundcloud.com/artist/sets/some-set",
+            true,
+            0,
+        ),
+        (
+            "https://on.soundcloud.com/AbCdE",
+            "https://on.soundcloud.com/AbCdE",
+            false,
+            0,
+        ),
+    ] {
+        let player = SoundCloudPlayer::from_input(input).expect(input);
+        assert_eq!(player.url, url);
+        assert_eq!(player.is_playlist, is_playlist, "{}", input);
+        assert_eq!(player.time, Duration::from_secs(secs), "{}", input);
+    }
+
+    for input in [
+        "https://soundcloud.com/",
+        "https://soundcloud.com/artist",
+        "https://soundcloud.com/artist/likes",
+        "https://soundcloud.com/artist/sets",
+        "https://soundcloud.com/discover/sets/charts",
+        "https://soundcloud.com/artist/some-track/comments",
+        "https://www.classicube.net/artist/some-track",
+    ] {
+        assert!(SoundCloudPlayer::from_input(input).is_err(), "{}", input);
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use classicube_helpers::{
+    async_manager,
+    color::{SILVER, TEAL},
+};
+use futures::{future::RemoteHandle, prelude::*};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::{
+    helpers::{parse_time, start_update_loop, SYNCED_MAX_LAG},
+    PlaybackState, PlayerTrait, VolumeMode,
+};
+use crate::{
+    api,
+    cef::{RustRefBrowser, TWITCH_PAGE_URL},
+    chat::Chat,
+    error::{bail, Result, ResultExt},
+    helpers::or_default,
+    options,
+};
+
+/// used if we can't look up a clip's length, they can't be longer
+const CLIP_MAX_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TwitchKind {
+    /// live stream of a channel
+    Channel(String),
+
+    /// past broadcast, "v" prefix stripped
+    Video(String),
+
+    /// clip slug, the embed can't be controlled
+    Clip(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwitchPlayer {
+    pub kind: TwitchKind,
+
+    /// only used for videos, live streams are always at the live edge
+    pub time: Duration,
+
+    // 0-1
+    volume: f32,
+    #[serde(deserialize_with = "or_default")]
+    volume_mode: VolumeMode,
+
+    autoplay: bool,
+    silent: bool,
+
+    #[serde(skip)]
+    pub update_loop_handle: Option<RemoteHandle<()>>,
+
+    #[serde(skip)]
+    clip_duration_handle: Option<RemoteHandle<()>>,
+
+    #[serde(skip)]
+    last_title: String,
+
+    #[serde(skip)]
+    pub finished: bool,
+
+    #[serde(skip)]
+    pub create_time: Option<Instant>,
+
+    /// created from a sync message, seek precisely once playback starts
+    #[serde(skip)]
+    pub synced: bool,
+
+    #[serde(skip)]
+    pub state: PlaybackState,
+}
+
+impl Default for TwitchPlayer {
+    fn default() -> Self {
+        Self {
+            kind: TwitchKind::Channel(String::new()),
+            time: Duration::from_millis(0),
+            volume: 1.0,
+            volume_mode: VolumeMode::Distance {
+                multiplier: 1.0,
+                distance: 28.0,
+            },
+            autoplay: true,
+            silent: false,
+            update_loop_handle: None,
+            clip_duration_handle: None,
+            last_title: String::new(),
+            finished: false,
+            create_time: None,
+            synced: false,
+            state: PlaybackState::Playing,
+        }
+    }
+}
+
+impl Clone for TwitchPlayer {
+    fn clone(&self) -> Self {
+        Self {
+            kind: self.kind.clone(),
+            time: self.time,
+            volume: self.volume,
+            volume_mode: self.volume_mode,
+            autoplay: self.autoplay,
+            silent: self.silent,
+            ..Default::default()
+        }
+    }
+}
+
+impl PlayerTrait for TwitchPlayer {
+    fn type_name(&self) -> &'static str {
+        "Twitch"
+    }
+
+    fn from_input(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            bail!("not http/https");
+        }
+
+        let (kind, time) = Self::parse_url(&url).chain_err(|| "couldn't match twitch url")?;
+
+        Ok(Self {
+            kind,
+            time,
+            ..Default::default()
+        })
+    }
+
+    fn on_create(&mut self) -> Result<String> {
+        debug!("TwitchPlayer on_create {:?}", self.kind);
+        self.create_time = Some(Instant::now());
+
+        let mut params = vec![("volume", format!("{}", self.volume))];
+
+        match &self.kind {
+            TwitchKind::Channel(channel) => {
+                params.push(("channel", channel.to_string()));
+            }
+            TwitchKind::Video(video) => {
+                params.push(("video", video.to_string()));
+                params.push(("time", format!("{}", self.time.as_secs())));
+            }
+            TwitchKind::Clip(clip) => {
+                params.push(("clip", clip.to_string()));
+            }
+        }
+
+        if self.autoplay {
+            params.push(("autoplay", "1".to_string()));
+        }
+
+        Ok(Url::parse_with_params(TWITCH_PAGE_URL, &params)?.into())
+    }
+
+    fn on_page_loaded(&mut self, entity_id: usize, browser: &RustRefBrowser) {
+        let (f, remote_handle) = start_update_loop(entity_id).remote_handle();
+        self.update_loop_handle = Some(remote_handle);
+        async_manager::spawn_local_on_main_thread(f);
+
+        // clips finish on a timer in the page, it needs their length
+        if let TwitchKind::Clip(clip) = &self.kind {
+            let clip = clip.clone();
+            let browser = browser.clone();
+            let (f, remote_handle) = async move {
+                let duration = api::twitch::clip_duration(&clip).await.unwrap_or_else(|e| {
+                    warn!("clip duration {:?}: {}", clip, e);
+                    CLIP_MAX_DURATION
+                });
+
+                let _ignore = Self::execute(
+                    &browser,
+                    &format!("setClipDuration({})", duration.as_secs_f32()),
+                );
+            }
+            .remote_handle();
+            self.clip_duration_handle = Some(remote_handle);
+            async_manager::spawn_local_on_main_thread(f);
+        }
+    }
+
+    fn on_title_change(&mut self, _entity_id: usize, browser: &RustRefBrowser, title: String) {
+        if self.last_title == title || title == "Twitch Loading" {
+            return;
+        }
+
+        if !self.silent {
+            Chat::print(format!("{TEAL}Now playing {SILVER}{title}"));
+        }
+
+        self.last_title = title;
+
+        if self.autoplay && self.is_video() {
+            if let Some(create_time) = self.create_time {
+                // if it took a long time to load
+                let lag = create_time.elapsed();
+                debug!("twitch video started playing after loading {:?}", lag);
+                let max_lag = if self.synced {
+                    SYNCED_MAX_LAG
+                } else {
+                    Duration::from_secs(10)
+                };
+                if lag > max_lag {
+                    warn!("slow twitch video load, seeking to {:?}", lag);
+                    let current_time = self.time + lag;
+                    let _ignore = self.set_current_time(browser, current_time);
+                }
+            }
+        }
+    }
+
+    fn get_current_time(&self) -> Result<Duration> {
+        if !self.is_video() {
+            bail!("live streams and clips don't have a time");
+        }
+
+        Ok(self.time)
+    }
+
+    fn set_current_time(&mut self, browser: &RustRefBrowser, time: Duration) -> Result<()> {
+        if !self.is_video() {
+            bail!("can only seek twitch videos");
+        }
+
+        Self::execute(browser, &format!("setCurrentTime({})", time.as_secs_f32()))?;
+        self.time = time;
+
+        Ok(())
+    }
+
+    fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// volume is a float between 0-1
+    fn set_volume(&mut self, browser: Option<&RustRefBrowser>, volume: f32) -> Result<()> {
+        if let Some(browser) = browser {
+            if (volume - self.volume).abs() > 0.0001 {
+                let volume_modifier = options::VOLUME.get()?;
+                Self::execute(browser, &format!("setVolume({})", volume * volume_modifier))?;
+            }
+        }
+
+        self.volume = volume;
+
+        Ok(())
+    }
+
+    fn get_volume_mode(&self) -> VolumeMode {
+        self.volume_mode
+    }
+
+    fn set_volume_mode(
+        &mut self,
+        browser: Option<&RustRefBrowser>,
+        mode: VolumeMode,
+    ) -> Result<()> {
+        if let Some(browser) = browser {
+            if let VolumeMode::Panning { pan, .. } = mode {
+                let _ignore = browser.execute_javascript_on_frame(
+                    "https://player.twitch.tv",
+                    format!(
+                        r#"
+                            if (typeof window.panner === "undefined") {{
+                                var video = document.getElementsByTagName("video")[0];
+                                var context = new AudioContext();
+                                var source = context.createMediaElementSource(video);
+                                var panner = context.createStereoPanner();
+                                source.connect(panner);
+                                panner.connect(context.destination);
+                                window.panner = panner;
+                                window.context = context;
+                            }}
+                            window.panner.pan.setTargetAtTime(
+                                {pan},
+                                window.context.currentTime,
+                                0.02
+                            );
+                        "#
+                    ),
+                );
+            } else {
+                let _ignore = browser.execute_javascript_on_frame(
+                    "https://player.twitch.tv",
+                    r#"
+                        if (typeof window.panner !== "undefined") {
+                            window.panner.pan.value = 0.0;
+                        }
+                    "#,
+                );
+            }
+        }
+
+        self.volume_mode = mode;
+        Ok(())
+    }
+
+    fn get_autoplay(&self) -> bool {
+        self.autoplay
+    }
+
+    fn set_autoplay(&mut self, _browser: Option<&RustRefBrowser>, autoplay: bool) -> Result<()> {
+        self.autoplay = autoplay;
+        Ok(())
+    }
+
+    fn get_url(&self) -> String {
+        match &self.kind {
+            TwitchKind::Channel(channel) => format!("https://www.twitch.tv/{channel}"),
+            TwitchKind::Video(video) => {
+                let secs = self.time.as_secs();
+                if secs == 0 {
+                    format!("https://www.twitch.tv/videos/{video}")
+                } else {
+                    format!("https://www.twitch.tv/videos/{video}?t={secs}s")
+                }
+            }
+            TwitchKind::Clip(clip) => format!("https://clips.twitch.tv/{clip}"),
+        }
+    }
+
+    fn get_title(&self) -> String {
+        self.last_title.clone()
+    }
+
+    fn is_finished_playing(&self) -> bool {
+        self.finished
+    }
+
+    fn set_playing(&mut self, browser: &RustRefBrowser, playing: bool) -> Result<()> {
+        if let TwitchKind::Clip(_) = self.kind {
+            bail!("can't pause twitch clips");
+        }
+
+        Self::execute(browser, &format!("setPlaying({playing})"))?;
+        Ok(())
+    }
+
+    fn set_silent(&mut self, silent: bool) -> Result<()> {
+        self.silent = silent;
+        Ok(())
+    }
+
+    fn get_playback_state(&self) -> PlaybackState {
+        self.state
+    }
+}
+
+impl TwitchPlayer {
+    pub fn is_video(&self) -> bool {
+        matches!(self.kind, TwitchKind::Video(_))
+    }
+
+    fn execute(browser: &RustRefBrowser, method: &str) -> Result<()> {
+        let code = format!("window.{method};");
+        browser.execute_javascript(code)?;
+        Ok(())
+    }
+}
+
+impl TwitchPlayer {
+    fn parse_url(url: &Url) -> Option<(TwitchKind, Duration)> {
+        let host_str = url.host_str()?;
+        let mut path_segments = url.path_segments()?.filter(|s| !s.is_empty());
+
+        if host_str == "clips.twitch.tv" {
+            let clip = path_segments.next()?;
+            return Self::is_slug(clip)
+                .then(|| (TwitchKind::Clip(clip.to_string()), Duration::ZERO));
+        }
+
+        if host_str != "twitch.tv" && host_str != "www.twitch.tv" && host_str != "m.twitch.tv" {
+            return None;
+        }
+
+        let first = path_segments.next()?;
+        let second = path_segments.next();
+        let third = path_segments.next();
+
+        match (first, second, third) {
+            // https://www.twitch.tv/videos/1234567890?t=1h2m3s
+            ("videos", Some(video), None) => {
+                let video = video.strip_prefix('v').unwrap_or(video);
+                if video.is_empty() || !video.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+
+                let query: HashMap<_, _> = url.query_pairs().collect();
+                let time = query
+                    .get("t")
+                    .and_then(|t| parse_time(t))
+                    .unwrap_or_default();
+
+                Some((TwitchKind::Video(video.to_string()), time))
+            }
+
+            // https://www.twitch.tv/channel/clip/SomeSlug-abc
+            (channel, Some("clip"), Some(clip)) if Self::is_channel(channel) => {
+                Self::is_slug(clip).then(|| (TwitchKind::Clip(clip.to_string()), Duration::ZERO))
+            }
+
+            // https://www.twitch.tv/channel
+            (channel, None, None) if Self::is_channel(channel) => {
+                Some((TwitchKind::Channel(channel.to_lowercase()), Duration::ZERO))
+            }
+
+            _ => None,
+        }
+    }
+
+    fn is_channel(name: &str) -> bool {
+        // pages on twitch.tv that look like channel names
+        const RESERVED: &[&str] = &[
+            "directory",
+            "downloads",
+            "p",
+            "search",
+            "settings",
+            "videos",
+        ];
+
+        Regex::new(r"^[A-Za-z0-9_]{3,25}$").unwrap().is_match(name)
+            && !RESERVED.contains(&name.to_lowercase().as_str())
+    }
+
+    fn is_slug(slug: &str) -> bool {
+        Regex::new(r"^[A-Za-z0-9_\-]+$").unwrap().is_match(slug)
+    }
+}
+
+#[test]
+fn test_twitch() {
+    for (input, kind) in [
+        (
+            "https://www.twitch.tv/SomeChannel",
+            TwitchKind::Channel("somechannel".into()),
+        ),
+        (
+            "https://twitch.tv/some_channel/",
+            TwitchKind::Channel("some_channel".into()),
+        ),
+        (
+            "https://m.twitch.tv/somechannel",
+            TwitchKind::Channel("somechannel".into()),
+        ),
+        (
+            "https://www.twitch.tv/videos/1234567890",
+            TwitchKind::Video("1234567890".into()),
+        ),
+        (
+            "https://www.twitch.tv/somechannel/clip/FunnyClip-abc_123",
+            TwitchKind::Clip("FunnyClip-abc_123".into()),
+        ),
+        (
+            "https://clips.twitch.tv/FunnyClip-abc_123",
+            TwitchKind::Clip("FunnyClip-abc_123".into()),
+        ),
+    ] {
+        let player = TwitchPlayer::from_input(input).expect(input);
+        assert_eq!(player.kind, kind);
+        assert_eq!(player.time, Duration::ZERO);
+    }
+
+    for (input, secs) in [
+        ("https://www.twitch.tv/videos/1234567890?t=1h2m3s", 3723),
+        ("https://www.twitch.tv/videos/1234567890?t=90s", 90),
+        ("https://www.twitch.tv/videos/1234567890?t=90", 90),
+    ] {
+        let player = TwitchPlayer::from_input(input).expect(input);
+        assert_eq!(player.kind, TwitchKind::Video("1234567890".into()));
+        assert_eq!(player.time, Duration::from_secs(secs));
+    }
+
+    for input in [
+        "https://www.twitch.tv/",
+        "https://www.twitch.tv/directory",
+        "https://www.twitch.tv/videos/abc",
+        "https://www.twitch.tv/somechannel/videos",
+        "https://www.youtube.com/somechannel",
+        "somechannel",
+    ] {
+        assert!(TwitchPlayer::from_input(input).is_err(), "{}", input);
+    }
+}
@@ -0,0 +1,170 @@
+//! Which domains screens may show, set by the server with a chat message like
+//! `url policy: allow youtube.com youtu.be` or `url policy: deny example.com`.
+
+use std::cell::RefCell;
+
+use classicube_helpers::{
+    async_manager,
+    color::{RED, SILVER},
+};
+use tracing::debug;
+use url::Url;
+
+use super::PlayerTrait;
+use crate::{
+    cef::TWITCH_PAGE_URL,
+    chat::Chat,
+    entity_manager::EntityManager,
+    error::{bail, Result},
+};
+
+thread_local!(
+    static POLICY: RefCell<Option<UrlPolicy>> = RefCell::default();
+);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlPolicy {
+    /// only these domains and their subdomains
+    Allow(Vec<String>),
+    /// anything but these domains and their subdomains
+    Deny(Vec<String>),
+}
+
+impl UrlPolicy {
+    /// `allow a.com b.com`, `deny a.com,b.com`, or `none` to remove it
+    pub fn parse(input: &str) -> Result<Option<Self>> {
+        let mut words = input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty());
+
+        let mode = words.next().unwrap_or_default().to_ascii_lowercase();
+        let domains = words
+            .map(|domain| {
+                domain
+                    .trim_start_matches("*.")
+                    .trim_end_matches('.')
+                    .to_ascii_lowercase()
+            })
+            .collect();
+
+        Ok(match mode.as_str() {
+            "allow" => Some(Self::Allow(domains)),
+            "deny" => Some(Self::Deny(domains)),
+            "none" | "clear" => None,
+            _ => bail!("unknown url policy {:?}", mode),
+        })
+    }
+
+    pub fn check(&self, url: &str) -> Result<()> {
+        if url.starts_with(TWITCH_PAGE_URL) {
+            return Ok(());
+        }
+
+        // our own data: pages, only http(s) goes anywhere
+        let Ok(url) = Url::parse(url) else {
+            return Ok(());
+        };
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Ok(());
+        }
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+
+        match self {
+            Self::Allow(domains) => {
+                if !domains.iter().any(|domain| matches_domain(&host, domain)) {
+                    bail!("{} isn't allowed on this server", host);
+                }
+            }
+
+            Self::Deny(domains) => {
+                if domains.iter().any(|domain| matches_domain(&host, domain)) {
+                    bail!("{} is denied on this server", host);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn matches_domain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+pub fn set(policy: Option<UrlPolicy>) {
+    debug!("url policy {:?}", policy);
+
+    POLICY.with(move |cell| {
+        *cell.borrow_mut() = policy;
+    });
+
+    close_denied_screens();
+}
+
+/// Screens that were opened before the policy changed.
+fn close_denied_screens() {
+    let denied: Vec<(usize, String)> = EntityManager::with_all_entities(|entities| {
+        entities
+            .values()
+            .filter_map(|entity| {
+                check(&entity.player.get_url())
+                    .err()
+                    .map(|e| (entity.id, e.to_string()))
+            })
+            .collect()
+    });
+    if denied.is_empty() {
+        return;
+    }
+
+    async_manager::spawn_local_on_main_thread(async move {
+        for (entity_id, reason) in denied {
+            let screen = EntityManager::get_name_by_entity_id(entity_id)
+                .unwrap_or_else(|| entity_id.to_string());
+            if EntityManager::remove_entity(entity_id).await.is_ok() {
+                Chat::print(format!(
+                    "{RED}Closed screen {SILVER}{screen}{RED}: {reason}"
+                ));
+            }
+        }
+    });
+}
+
+/// Errors if the server's policy doesn't allow this url.
+pub fn check(url: &str) -> Result<()> {
+    POLICY.with(|cell| {
+        let policy = &*cell.borrow();
+        policy.as_ref().map_or(Ok(()), |policy| policy.check(url))
+    })
+}
+
+#[test]
+fn test_url_policy() {
+    assert_eq!(UrlPolicy::parse("none").unwrap(), None);
+    assert!(UrlPolicy::parse("maybe a.com").is_err());
+
+    let allow = UrlPolicy::parse("allow YouTube.com, *.twitch.tv")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        allow,
+        UrlPolicy::Allow(vec!["youtube.com".to_string(), "twitch.tv".to_string()])
+    );
+    assert!(allow.check("https://youtube.com/watch").is_ok());
+    assert!(allow.check("https://www.youtube.com/watch").is_ok());
+    assert!(allow.check("https://clips.twitch.tv/abc").is_ok());
+    assert!(allow.check("https://notyoutube.com/").is_err());
+    assert!(allow.check("https://youtube.com.evil.org/").is_err());
+    assert!(allow.check("http://1.2.3.4/").is_err());
+    assert!(allow.check("data:text/html,hi").is_ok());
+    assert!(allow.check(TWITCH_PAGE_URL).is_ok());
+
+    let deny = UrlPolicy::parse("deny example.com").unwrap().unwrap();
+    assert!(deny.check("https://example.com/").is_err());
+    assert!(deny.check("https://a.b.example.com/").is_err());
+    assert!(deny.check("https://example.org/").is_ok());
+    assert!(deny.check("https://myexample.com/").is_ok());
+}
@@ -47,6 +47,9 @@ async fn fade_all() -> Result<()> {
         for entity in entities.values_mut() {
             match &mut entity.player {
                 Player::YouTube(player) => drop(player.update_loop_handle.take()),
+                Player::Twitch(player) => drop(player.update_loop_handle.take()),
+                Player::Embed(player) => drop(player.update_loop_handle.take()),
+                Player::SoundCloud(player) => drop(player.update_loop_handle.take()),
                 Player::Dash(player) => drop(player.update_loop_handle.take()),
                 Player::Hls(player) => drop(player.update_loop_handle.take()),
                 Player::Media(player) => drop(player.update_loop_handle.take()),
@@ -13,12 +13,12 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 use url::Url;
 
-use super::{helpers::start_update_loop, PlayerTrait, VolumeMode};
+use super::{
+    helpers::{start_update_loop, SYNCED_MAX_LAG},
+    PlaybackState, PlayerTrait, VolumeMode,
+};
 use crate::{
-    cef::{RustRefBrowser, RustV8Value},
-    chat::Chat,
-    error::{bail, Result},
-    options,
+    cef::RustRefBrowser, chat::Chat, error::Result, helpers::or_default, options,
     options::SUBTITLES,
 };
 
@@ -32,12 +32,13 @@ pub struct YouTubePlayer {
 
     // 0-1
     volume: f32,
+    #[serde(deserialize_with = "or_default")]
     volume_mode: VolumeMode,
 
     autoplay: bool,
     should_loop: bool,
     silent: bool,
-    speed: f32,
+    pub speed: f32,
 
     #[serde(skip)]
     pub update_loop_handle: Option<RemoteHandle<()>>,
@@ -50,6 +51,12 @@ pub struct YouTubePlayer {
 
     #[serde(skip)]
     pub create_time: Option<Instant>,
+
+    /// created from a sync message, seek precisely once playback starts
+    #[serde(skip)]
+    pub synced: bool,
+    #[serde(skip)]
+    pub state: PlaybackState,
 }
 
 impl Default for YouTubePlayer {
@@ -71,6 +78,8 @@ impl Default for YouTubePlayer {
             last_title: String::new(),
             finished: false,
             create_time: None,
+            synced: false,
+            state: PlaybackState::Playing,
         }
     }
 }
@@ -170,7 +179,12 @@ impl PlayerTrait for YouTubePlayer {
                 let lag = now - create_time;
                 debug!("video started playing after loading {:?}", lag);
                 // TODO delay everyone a couple seconds then start playing video!
-                if lag > Duration::from_secs(10) {
+                let max_lag = if self.synced {
+                    SYNCED_MAX_LAG
+                } else {
+                    Duration::from_secs(10)
+                };
+                if lag > max_lag {
                     // TODO don't do this if longer than video duration
                     warn!("slow video load, seeking to {:?}", lag);
                     // seek to current time
@@ -305,6 +319,10 @@ impl PlayerTrait for YouTubePlayer {
         Ok(())
     }
 
+    fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
     fn set_speed(&mut self, browser: Option<&RustRefBrowser>, speed: f32) -> Result<()> {
         if let Some(browser) = browser {
             Self::execute(browser, &format!("setPlaybackRate({speed})"))?;
@@ -313,45 +331,23 @@ impl PlayerTrait for YouTubePlayer {
         self.speed = speed;
         Ok(())
     }
-}
-
-impl YouTubePlayer {
-    pub async fn real_is_finished_playing(browser: &RustRefBrowser) -> Result<bool> {
-        let ended = match Self::eval(browser, "playerFinished").await? {
-            RustV8Value::Bool(ended) => ended,
-
-            other => {
-                bail!("non-bool js value {:?}", other);
-            }
-        };
 
-        Ok(ended)
+    fn set_playback_rate(&self, browser: &RustRefBrowser, rate: f32) -> Result<()> {
+        Self::execute(browser, &format!("setPlaybackRate({rate})"))?;
+        Ok(())
     }
 
-    pub async fn get_real_time(browser: &RustRefBrowser) -> Result<Duration> {
-        let seconds = match Self::eval(browser, "getCurrentTime()").await? {
-            RustV8Value::Double(seconds) => seconds as f32,
-            RustV8Value::Int(seconds) => seconds as f32,
-            RustV8Value::UInt(seconds) => seconds as f32,
-
-            other => {
-                bail!("non-number js value {:?}", other);
-            }
-        };
-
-        Ok(Duration::from_secs_f32(seconds))
+    fn get_playback_state(&self) -> PlaybackState {
+        self.state
     }
+}
 
+impl YouTubePlayer {
     fn execute(browser: &RustRefBrowser, method: &str) -> Result<()> {
         let code = format!("window.{method};");
         browser.execute_javascript(code)?;
         Ok(())
     }
-
-    async fn eval(browser: &RustRefBrowser, method: &str) -> Result<RustV8Value> {
-        let code = format!("window.{method};");
-        browser.eval_javascript(code).await
-    }
 }
 
 impl YouTubePlayer {
@@ -4,7 +4,12 @@ use classicube_helpers::{async_manager, color::RED, WithInner};
 use classicube_sys::{Server, String_AppendConst};
 use tracing::{debug, error};
 
-use crate::{cef::Cef, chat::Chat, entity_manager::EntityManager, player};
+use crate::{
+    cef::Cef,
+    chat::{hidden_communication::encoding, Chat},
+    entity_manager::{self, EntityManager},
+    player,
+};
 
 thread_local!(
     static PLUGIN: RefCell<Option<Plugin>> = const { RefCell::new(None) };
@@ -31,7 +36,8 @@ impl Plugin {
 
             Chat::print(format!("Loading Cef v{}", env!("CARGO_PKG_VERSION")));
 
-            let append_app_name = CString::new(format!(" {APP_NAME}")).unwrap();
+            let append_app_name =
+                CString::new(format!(" {APP_NAME} {}", encoding::schema_range_tag())).unwrap();
             let c_str = append_app_name.as_ptr();
             unsafe {
                 String_AppendConst(&mut Server.AppName, c_str);
@@ -84,6 +90,7 @@ impl Plugin {
                 }
 
                 plugin.entity_manager.on_new_map_loaded();
+                entity_manager::layout::on_new_map_loaded();
                 plugin.chat.on_new_map_loaded();
                 player::on_new_map_loaded();
             })